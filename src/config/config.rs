@@ -1,46 +1,89 @@
-use super::{plugin::Utility, Object};
+use super::error::Diagnostic;
+use super::plugin::ValueUtility;
+use super::schema::{ClassPattern, Color, CorePlugins, FilterValue, SafelistEntry};
+use super::{theme, Object};
+use indexmap::IndexMap;
 use serde_json::{Map, Value};
 use std::collections::HashMap;
 
 #[derive(Debug, Clone)]
 pub struct FontSize {
     pub value: String,
-    pub line_height: String,
+    pub line_height: Option<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Container {
+    pub center: bool,
+    // screen (or `DEFAULT`) -> horizontal padding
+    pub padding: HashMap<String, String>,
 }
 
 #[derive(Debug, Clone, Default)]
 pub struct Config {
     pub font_size: HashMap<String, FontSize>,
     pub font_weight: HashMap<String, String>,
+    pub font_family: HashMap<String, String>,
+    #[allow(dead_code)]
     pub base: Object,
     pub utility: Object,
-    pub spacing: Map<String, Value>,
+    pub spacing: IndexMap<String, String>,
     pub line_height: HashMap<String, String>,
     pub aspect_ratio: HashMap<String, String>,
-    pub break_point: Map<String, Value>,
+    // `break-before`, `break-inside` and `break-after` -> their classes.
+    pub break_point: Object,
+    pub screens: IndexMap<String, String>,
+    pub container: Container,
+    #[allow(dead_code)]
     pub float: Map<String, Value>,
-    pub color: Map<String, Value>,
-    pub plugins: Vec<Utility>,
+    pub color: IndexMap<String, Color>,
+    pub background_image: HashMap<String, String>,
+    pub background_size: HashMap<String, String>,
+    pub background_position: HashMap<String, String>,
+    pub gradient_color_stop_positions: HashMap<String, String>,
+    pub stroke_width: HashMap<String, String>,
+    pub opacity: HashMap<String, String>,
+    pub box_shadow: HashMap<String, String>,
+    pub list_style_type: HashMap<String, String>,
+    pub list_style_image: HashMap<String, String>,
+    pub outline_width: HashMap<String, String>,
+    pub outline_offset: HashMap<String, String>,
+    pub transition_duration: HashMap<String, String>,
+    pub filter: IndexMap<String, IndexMap<String, FilterValue>>,
+    pub variants: IndexMap<String, Vec<String>>,
+    // Prefix of every utility class, e.g. `tw-` for `tw-mt-4`.
+    pub prefix: String,
+    // Between variants and the utility, `:` in `hover:underline`.
+    pub separator: String,
+    // Classes generated whether or not they are found in the content.
+    pub safelist: Vec<SafelistEntry>,
+    // Classes never generated, even when found in the content.
+    pub blocklist: Vec<ClassPattern>,
+    pub plugins: Object,
+    // `.class` -> style object, registered from the input stylesheet.
+    pub components: Map<String, Value>,
+    pub custom_utilities: Map<String, Value>,
+    // Families of classes declared in `utilities`, by class prefix.
+    pub value_utilities: IndexMap<String, ValueUtility>,
+    // Plugins removed from the registry are never generated.
+    pub core_plugins: CorePlugins,
+    pub preflight: bool,
+    // Reference theme values through `var(--color-blue-500)` and friends.
+    pub css_variables: bool,
+    // The config as it was loaded, for `theme()` lookups.
+    pub theme: Map<String, Value>,
+    // Warnings found while loading, such as unknown keys.
+    pub diagnostics: Vec<Diagnostic>,
 }
 
 impl Config {
-    pub fn get_obj<'a>(
-        data: &'a Map<String, Value>,
-        data_key: &'a str,
-    ) -> Option<(&'a str, &'a str)> {
-        let display = data.get(data_key)?.as_object()?;
-        let (key, value) = display.iter().next()?;
-        let value = value.as_str()?;
-        Some((key, value))
-    }
-
     pub fn get_plugin_value(
         &self,
         data: &Map<String, Value>,
         data_key: &str,
         key_val: &str,
         is_negative: bool,
-    ) -> Option<String> {
+    ) -> Option<Vec<(String, String)>> {
         let item = data.get(data_key)?;
         let properties = item.as_array()?;
 
@@ -55,7 +98,7 @@ impl Config {
 
         let variant: Option<&str> = match self.utility.get(name) {
             Some(val) => val.get(key_val)?.as_str(),
-            None => self.spacing.get(key_val)?.as_str(),
+            None => self.spacing.get(key_val).map(String::as_str),
         };
 
         let value = if is_negative {
@@ -64,12 +107,16 @@ impl Config {
             variant?.to_string()
         };
 
-        let mut css_properties_value = String::new();
-        for prop in properties {
-            let prop_val = &format!("\t{}: {};\n", prop.as_str()?, value);
-            css_properties_value.push_str(prop_val);
-        }
-        Some(css_properties_value)
+        properties
+            .iter()
+            .map(|prop| Some((prop.as_str()?.to_string(), value.clone())))
+            .collect()
+    }
+
+    /// Value of a dotted theme path such as `colors.blue.500`, or a message
+    /// explaining why the path is invalid.
+    pub fn get_theme(&self, path: &str) -> Result<String, String> {
+        theme::lookup(&self.theme, path)
     }
 
     pub fn get_font_size(&self, key: &str) -> Option<&FontSize> {
@@ -88,15 +135,48 @@ impl Config {
         self.aspect_ratio.get(key)
     }
 
-    pub fn get_break_point(&self, key: &str) -> Option<&Value> {
-        self.break_point.get(key)
+    /// The style object of a `break-*` class, with the plugin it belongs to.
+    pub fn get_break_point(&self, key: &str) -> Option<(&str, &Value)> {
+        self.break_point
+            .iter()
+            .find_map(|(plugin, classes)| Some((plugin.as_str(), classes.get(key)?)))
+    }
+
+    /// Selector templates (`&:hover`), any of which matches, or at-rule
+    /// (`@media print`) of a variant.
+    pub fn get_variant(&self, name: &str) -> Option<&Vec<String>> {
+        self.variants.get(name)
     }
 
-    pub fn get_color_map(&self, key: &str) -> Option<&Map<String, Value>> {
-        self.color.get(key)?.as_object()
+    /// Registration order of a variant, used to sort variant rules.
+    pub fn get_variant_order(&self, name: &str) -> Option<usize> {
+        self.variants.get_index_of(name)
+    }
+
+    pub fn get_filter(&self, name: &str, key: &str) -> Option<&FilterValue> {
+        self.filter.get(name)?.get(key)
+    }
+
+    pub fn get_color_map(&self, key: &str) -> Option<&IndexMap<String, String>> {
+        match self.color.get(key)? {
+            Color::Palette(shades) => Some(shades),
+            Color::Single(_) => None,
+        }
     }
 
     pub fn get_color_str(&self, key: &str) -> Option<&str> {
-        self.color.get(key).unwrap().as_str()
+        match self.color.get(key)? {
+            Color::Single(color) => Some(color),
+            Color::Palette(_) => None,
+        }
+    }
+
+    /// Resolve a palette key such as `black` or `blue-500`.
+    pub fn get_color(&self, key: &str) -> Option<&str> {
+        if let Some(color) = self.get_color_str(key) {
+            return Some(color);
+        }
+        let (name, shade) = key.rsplit_once('-')?;
+        self.get_color_map(name)?.get(shade).map(String::as_str)
     }
 }
@@ -0,0 +1,75 @@
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    // The config is still usable, e.g. an unknown key.
+    Warning,
+    // The entry can't be used, e.g. a number where a style object belongs.
+    Error,
+}
+
+/// A problem found in the config, at a dotted path such as `spacing.4`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub path: String,
+    pub message: String,
+}
+
+impl Diagnostic {
+    pub fn error(path: &str, message: String) -> Self {
+        Self {
+            severity: Severity::Error,
+            path: path.to_string(),
+            message,
+        }
+    }
+
+    pub fn warning(path: &str, message: String) -> Self {
+        Self {
+            severity: Severity::Warning,
+            path: path.to_string(),
+            message,
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.path, self.message)
+    }
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Json(serde_json::Error),
+    NotAnObject,
+    // Every diagnostic of a config with at least one error. Only the errors
+    // are displayed, one per line.
+    Invalid(Vec<Diagnostic>),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Json(e) => write!(f, "{}", e),
+            Error::NotAnObject => write!(f, "the config must be an object"),
+            Error::Invalid(diagnostics) => {
+                let lines: Vec<String> = diagnostics
+                    .iter()
+                    .filter(|diagnostic| diagnostic.severity == Severity::Error)
+                    .map(ToString::to_string)
+                    .collect();
+                write!(f, "{}", lines.join("\n"))
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<serde_json::Error> for Error {
+    fn from(e: serde_json::Error) -> Self {
+        Error::Json(e)
+    }
+}
@@ -0,0 +1,40 @@
+use indexmap::IndexMap;
+use serde_json::{Map, Value};
+
+use super::schema::{section, FilterValue};
+
+// class -> theme key, listed in the order the functions are composed into
+// `filter`. Every class writes its function to `--tw-{class}`.
+pub const FILTERS: [(&str, &str); 9] = [
+    ("blur", "blur"),
+    ("brightness", "brightness"),
+    ("contrast", "contrast"),
+    ("grayscale", "grayscale"),
+    ("hue-rotate", "hueRotate"),
+    ("invert", "invert"),
+    ("saturate", "saturate"),
+    ("sepia", "sepia"),
+    ("drop-shadow", "dropShadow"),
+];
+
+// backdrop-{class} -> theme key, composed into `backdrop-filter` through
+// `--tw-backdrop-{class}`.
+pub const BACKDROP_FILTERS: [(&str, &str); 9] = [
+    ("blur", "blur"),
+    ("brightness", "brightness"),
+    ("contrast", "contrast"),
+    ("grayscale", "grayscale"),
+    ("hue-rotate", "hueRotate"),
+    ("invert", "invert"),
+    ("opacity", "opacity"),
+    ("saturate", "saturate"),
+    ("sepia", "sepia"),
+];
+
+pub fn extract(obj: &'_ Map<String, Value>) -> IndexMap<String, IndexMap<String, FilterValue>> {
+    FILTERS
+        .iter()
+        .chain(BACKDROP_FILTERS.iter())
+        .map(|(_, key)| (key.to_string(), section(obj, key)))
+        .collect()
+}
@@ -0,0 +1,293 @@
+use serde_json::{Map, Value};
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use super::plugin::plugin_key;
+use super::theme::section_name;
+use super::tokens;
+use super::{parse, parse_object, Config};
+
+/// Config files picked up from the working directory when no `--config`
+/// is given. The format follows the extension.
+const CONFIG_FILES: [&str; 4] = [
+    "tailwind.config.json",
+    "tailwind.json",
+    "tailwind.json5",
+    "tailwind.toml",
+];
+
+const DEFAULT_CONFIG: &str = include_str!("../default-config.json");
+
+#[derive(Debug)]
+pub enum Error {
+    Io(PathBuf, std::io::Error),
+    // Syntax errors, at a 1-based line and column of the file.
+    Parse {
+        path: PathBuf,
+        line: usize,
+        column: usize,
+        message: String,
+    },
+    NotAnObject(PathBuf),
+    // `presets` isn't a list of paths, or a preset includes itself.
+    Presets(PathBuf, String),
+    // `tokens` isn't a path, or the design tokens can't be imported.
+    Tokens(PathBuf, String),
+    // The merged config doesn't have the shape the utilities expect.
+    Invalid(PathBuf, super::Error),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(path, e) => write!(f, "{}: {}", path.display(), e),
+            Error::Parse {
+                path,
+                line,
+                column,
+                message,
+            } => write!(f, "{}:{}:{}: {}", path.display(), line, column, message),
+            Error::NotAnObject(path) => {
+                write!(f, "{}: the config must be an object", path.display())
+            }
+            Error::Presets(path, message) | Error::Tokens(path, message) => {
+                write!(f, "{}: {}", path.display(), message)
+            }
+            Error::Invalid(path, e) => {
+                let lines: Vec<String> = e
+                    .to_string()
+                    .lines()
+                    .map(|line| format!("{}: {}", path.display(), line))
+                    .collect();
+                write!(f, "{}", lines.join("\n"))
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+pub fn discover() -> Option<PathBuf> {
+    CONFIG_FILES
+        .iter()
+        .map(PathBuf::from)
+        .find(|path| path.is_file())
+}
+
+// Merge `value` into `target`: objects key by key, anything else replaces.
+fn deep_merge(target: &mut Value, value: Value) {
+    match (target, value) {
+        (Value::Object(target), Value::Object(value)) => {
+            for (key, value) in value {
+                deep_merge(target.entry(key).or_insert(Value::Null), value);
+            }
+        }
+        (target, value) => *target = value,
+    }
+}
+
+// Apply a `corePlugins` value over the previous one: a list replaces it,
+// toggles are applied to it.
+fn merge_core_plugins(target: &mut Value, value: Value) {
+    match (target, value) {
+        (Value::Array(plugins), Value::Object(toggles)) => {
+            for (name, enabled) in toggles {
+                plugins.retain(|plugin| {
+                    plugin_key(plugin.as_str().unwrap_or_default()) != plugin_key(&name)
+                });
+                if enabled == Value::Bool(true) {
+                    plugins.push(Value::String(name));
+                }
+            }
+        }
+        (Value::Object(target), Value::Object(toggles)) => target.extend(toggles),
+        (target, value) => *target = value,
+    }
+}
+
+/// Apply configs to the defaults, lowest precedence first, the way Tailwind
+/// resolves presets. Sections under `theme` replace the previous ones, and
+/// the sections under `theme.extend` of every config are then merged into
+/// them in order. `plugins`, `utilities` and `variants` add to the previous
+/// ones, `corePlugins` toggles apply on top of the previous ones and other
+/// top-level keys replace.
+fn merge(config: &mut Map<String, Value>, configs: Vec<Map<String, Value>>) {
+    let mut extends = Vec::new();
+    for user in configs {
+        for (key, value) in user {
+            match key.as_str() {
+                "theme" => {
+                    let mut theme = match value {
+                        Value::Object(theme) => theme,
+                        _ => continue,
+                    };
+                    extends.extend(theme.remove("extend"));
+                    for (section, value) in theme {
+                        config.insert(section_name(&section).to_string(), value);
+                    }
+                }
+                "plugins" | "utilities" | "variants" => match (config.get_mut(&key), value) {
+                    (Some(Value::Object(entries)), Value::Object(value)) => entries.extend(value),
+                    (_, value) => {
+                        config.insert(key, value);
+                    }
+                },
+                "corePlugins" => {
+                    merge_core_plugins(config.entry(key).or_insert(Value::Null), value)
+                }
+                // Already resolved by `collect`.
+                "presets" => {}
+                _ => {
+                    config.insert(key, value);
+                }
+            }
+        }
+    }
+    for extend in extends {
+        if let Value::Object(extend) = extend {
+            for (section, value) in extend {
+                let target = config
+                    .entry(section_name(&section).to_string())
+                    .or_insert(Value::Null);
+                deep_merge(target, value);
+            }
+        }
+    }
+}
+
+// 1-based line and column of a byte offset.
+fn line_column(source: &str, offset: usize) -> (usize, usize) {
+    let before = &source[..offset.min(source.len())];
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    (
+        before.matches('\n').count() + 1,
+        before[line_start..].chars().count() + 1,
+    )
+}
+
+// Parse a config in the format given by the extension of `path`: TOML,
+// JSON5 or JSON.
+fn parse_source(path: &Path, source: &str) -> Result<Value, Error> {
+    let error = |(line, column), message: &str| Error::Parse {
+        path: path.into(),
+        line,
+        column,
+        message: message.to_string(),
+    };
+    match path.extension().and_then(|extension| extension.to_str()) {
+        Some("toml") => toml::from_str(source).map_err(|e| {
+            let position = line_column(source, e.span().map_or(0, |span| span.start));
+            error(position, e.message().trim())
+        }),
+        Some("json5") => json5::from_str(source).map_err(|e| match e {
+            json5::Error::Message { msg, location } => {
+                let position = location.map_or((1, 1), |l| (l.line, l.column));
+                // Parse errors come with a code snippet, keep the message.
+                let message = msg.rsplit("= ").next().unwrap_or(&msg);
+                error(position, message.trim())
+            }
+        }),
+        _ => serde_json::from_str(source).map_err(|e| {
+            let message = e.to_string();
+            let message = match message.rsplit_once(" at line ") {
+                Some((message, _)) => message,
+                None => &message,
+            };
+            error((e.line(), e.column()), message)
+        }),
+    }
+}
+
+fn read_object(path: &Path) -> Result<Map<String, Value>, Error> {
+    let source = std::fs::read_to_string(path).map_err(|e| Error::Io(path.into(), e))?;
+    match parse_source(path, &source)? {
+        Value::Object(obj) => Ok(obj),
+        _ => Err(Error::NotAnObject(path.into())),
+    }
+}
+
+/// The theme sections imported from the W3C design tokens file at `path`.
+pub fn read_tokens(path: &Path) -> Result<Map<String, Value>, Error> {
+    let tokens = read_object(path)?;
+    tokens::theme(&tokens).map_err(|message| Error::Tokens(path.into(), message))
+}
+
+// Replace the `tokens` path of the config at `path` with the theme sections
+// imported from it, under `theme.extend` and below the sections written
+// there.
+fn import_tokens(path: &Path, config: &mut Map<String, Value>) -> Result<(), Error> {
+    let tokens = match config.remove("tokens") {
+        None => return Ok(()),
+        Some(Value::String(tokens)) => tokens,
+        Some(_) => {
+            let message = "`tokens` must be a file path".to_string();
+            return Err(Error::Tokens(path.into(), message));
+        }
+    };
+    let dir = path.parent().unwrap_or_else(|| Path::new(""));
+    let mut extend = Value::Object(read_tokens(&dir.join(tokens))?);
+    let theme = config
+        .entry("theme")
+        .or_insert_with(|| Value::Object(Map::new()));
+    if let Value::Object(theme) = theme {
+        if let Some(user) = theme.remove("extend") {
+            deep_merge(&mut extend, user);
+        }
+        theme.insert("extend".to_string(), extend);
+    }
+    Ok(())
+}
+
+/// The config at `path` after its presets, lowest precedence first: each
+/// preset with its own presets before it, then the config itself. Preset
+/// paths are relative to the file that lists them.
+fn collect(
+    path: &Path,
+    loading: &mut Vec<PathBuf>,
+    configs: &mut Vec<Map<String, Value>>,
+) -> Result<(), Error> {
+    let id = std::fs::canonicalize(path).unwrap_or_else(|_| path.into());
+    if loading.contains(&id) {
+        let message = "the preset includes itself".to_string();
+        return Err(Error::Presets(path.into(), message));
+    }
+    let mut config = read_object(path)?;
+    import_tokens(path, &mut config)?;
+    let invalid = || {
+        let message = "`presets` must be a list of file paths".to_string();
+        Error::Presets(path.into(), message)
+    };
+    let presets = match config.get("presets") {
+        None => Vec::new(),
+        Some(Value::Array(presets)) => presets
+            .iter()
+            .map(|preset| preset.as_str().map(PathBuf::from))
+            .collect::<Option<Vec<_>>>()
+            .ok_or_else(invalid)?,
+        Some(_) => return Err(invalid()),
+    };
+
+    loading.push(id);
+    let dir = path.parent().unwrap_or_else(|| Path::new(""));
+    for preset in presets {
+        collect(&dir.join(preset), loading, configs)?;
+    }
+    loading.pop();
+    configs.push(config);
+    Ok(())
+}
+
+/// The default config with the user config at `path`, and its presets,
+/// merged over it.
+pub fn load(path: Option<&Path>) -> Result<Config, Error> {
+    let path = match path {
+        Some(path) => path,
+        None => return Ok(parse(DEFAULT_CONFIG).expect("default config is valid")),
+    };
+    let mut config: Map<String, Value> =
+        serde_json::from_str(DEFAULT_CONFIG).expect("default config is valid");
+    let mut configs = Vec::new();
+    collect(path, &mut Vec::new(), &mut configs)?;
+    merge(&mut config, configs);
+    parse_object(config).map_err(|e| Error::Invalid(path.into(), e))
+}
@@ -1,17 +1,33 @@
 #[allow(clippy::module_inception)]
 mod config;
+mod error;
+mod filter;
+mod loader;
+mod palette;
 mod parser;
 mod plugin;
+mod schema;
+mod theme;
+mod tokens;
 mod utility;
+mod validate;
 
 pub use config::Config;
+pub use config::Container;
 pub use config::FontSize;
-pub use parser::parse;
+pub use error::Error;
+pub use filter::{BACKDROP_FILTERS, FILTERS};
+pub use loader::{discover, load, read_tokens};
+pub use parser::{parse, parse_object};
+pub use plugin::{Mode, CORE_PLUGINS};
+pub use schema::{ClassPattern, Color, FilterValue, SafelistEntry};
 
+use indexmap::IndexMap;
 use serde_json::{Map, Value};
-use std::collections::HashMap;
 
-pub type Object = HashMap<String, Map<String, Value>>;
+// Sections keep the order they were registered in, which is the order their
+// utilities are emitted in.
+pub type Object = IndexMap<String, Map<String, Value>>;
 
 pub fn get_object(obj: &Map<String, Value>, key: &str) -> Map<String, Value> {
     if obj.get(key).is_none() || obj.get(key).unwrap().as_object().is_none() {
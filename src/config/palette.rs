@@ -0,0 +1,173 @@
+use serde_json::{Map, Value};
+
+use super::error::Diagnostic;
+use super::validate::describe;
+
+// Lightness of each shade for a base color at the lightness of `500`, and
+// chroma relative to the base color. Lighter and darker shades are scaled
+// so that `500` is the base color itself.
+const SHADES: [(&str, f64, f64); 11] = [
+    ("50", 0.975, 0.07),
+    ("100", 0.945, 0.15),
+    ("200", 0.895, 0.28),
+    ("300", 0.825, 0.5),
+    ("400", 0.72, 0.8),
+    ("500", 0.63, 1.0),
+    ("600", 0.55, 1.05),
+    ("700", 0.48, 0.95),
+    ("800", 0.415, 0.8),
+    ("900", 0.365, 0.65),
+    ("950", 0.27, 0.45),
+];
+
+// Lightness, chroma and hue in degrees.
+type Oklch = (f64, f64, f64);
+
+// Linear sRGB channels of a `#rgb` or `#rrggbb` color.
+fn parse_hex(color: &str) -> Option<[f64; 3]> {
+    let hex = color.strip_prefix('#')?;
+    let digits: Vec<u32> = hex.chars().map(|c| c.to_digit(16)).collect::<Option<_>>()?;
+    let channels: Vec<u32> = match digits.len() {
+        3 => digits.iter().map(|d| d * 17).collect(),
+        6 => digits.chunks(2).map(|d| d[0] * 16 + d[1]).collect(),
+        _ => return None,
+    };
+    let linear = |c: u32| {
+        let c = c as f64 / 255.0;
+        match c <= 0.04045 {
+            true => c / 12.92,
+            false => ((c + 0.055) / 1.055).powf(2.4),
+        }
+    };
+    Some([
+        linear(channels[0]),
+        linear(channels[1]),
+        linear(channels[2]),
+    ])
+}
+
+fn to_oklch([r, g, b]: [f64; 3]) -> Oklch {
+    let l = (0.4122214708 * r + 0.5363325363 * g + 0.0514459929 * b).cbrt();
+    let m = (0.2119034982 * r + 0.6806995451 * g + 0.1073969566 * b).cbrt();
+    let s = (0.0883024619 * r + 0.2817188376 * g + 0.6299787005 * b).cbrt();
+    let a = 1.9779984951 * l - 2.4285922050 * m + 0.4505937099 * s;
+    let b = 0.0259040371 * l + 0.7827717662 * m - 0.8086757660 * s;
+    (
+        0.2104542553 * l + 0.7936177850 * m - 0.0040720468 * s,
+        a.hypot(b),
+        b.atan2(a).to_degrees().rem_euclid(360.0),
+    )
+}
+
+fn to_linear_srgb((lightness, chroma, hue): Oklch) -> [f64; 3] {
+    let (a, b) = (
+        chroma * hue.to_radians().cos(),
+        chroma * hue.to_radians().sin(),
+    );
+    let l = (lightness + 0.3963377774 * a + 0.2158037573 * b).powi(3);
+    let m = (lightness - 0.1055613458 * a - 0.0638541728 * b).powi(3);
+    let s = (lightness - 0.0894841775 * a - 1.2914855480 * b).powi(3);
+    [
+        4.0767416621 * l - 3.3077115913 * m + 0.2309699292 * s,
+        -1.2684380046 * l + 2.6097574011 * m - 0.3413193965 * s,
+        -0.0041960863 * l - 0.7034186147 * m + 1.7076147010 * s,
+    ]
+}
+
+fn in_srgb(color: Oklch) -> bool {
+    to_linear_srgb(color)
+        .iter()
+        .all(|channel| (-1e-4..=1.0 + 1e-4).contains(channel))
+}
+
+// Lower the chroma until the color fits in sRGB, keeping its lightness and
+// hue.
+fn fit_srgb((lightness, chroma, hue): Oklch) -> Oklch {
+    if in_srgb((lightness, chroma, hue)) {
+        return (lightness, chroma, hue);
+    }
+    let (mut low, mut high) = (0.0, chroma);
+    while high - low > 1e-4 {
+        let mid = (low + high) / 2.0;
+        match in_srgb((lightness, mid, hue)) {
+            true => low = mid,
+            false => high = mid,
+        }
+    }
+    (lightness, low, hue)
+}
+
+fn round(value: f64, digits: i32) -> f64 {
+    let factor = 10f64.powi(digits);
+    (value * factor).round() / factor
+}
+
+fn format_oklch((lightness, chroma, hue): Oklch) -> String {
+    let hue = match round(chroma, 3) == 0.0 {
+        true => 0.0,
+        false => round(hue, 2),
+    };
+    format!(
+        "oklch({}% {} {})",
+        round(lightness * 100.0, 1),
+        round(chroma, 3),
+        hue
+    )
+}
+
+/// Shades `50` to `950` around `base`, which becomes `500`: lightness
+/// follows a fixed curve, chroma peaks around the base color and hue stays
+/// the same. Shades that would fall outside sRGB lose chroma.
+pub fn generate(base: &str) -> Option<Map<String, Value>> {
+    let (lightness, chroma, hue) = to_oklch(parse_hex(base)?);
+    let (_, base_lightness, _) = SHADES[5];
+    let shades = SHADES.iter().map(|(shade, target, chroma_factor)| {
+        let lightness = match *target > base_lightness {
+            true => 1.0 - (1.0 - target) * (1.0 - lightness) / (1.0 - base_lightness),
+            false => target * lightness / base_lightness,
+        };
+        let color = match *shade {
+            "500" => (lightness, chroma, hue),
+            _ => fit_srgb((lightness, chroma * chroma_factor, hue)),
+        };
+        (shade.to_string(), Value::String(format_oklch(color)))
+    });
+    Some(shades.collect())
+}
+
+/// Replace `{ "generate": "#3b82f6" }` entries of `color` with their
+/// generated shades. Shades written next to `generate` take precedence.
+pub fn expand(obj: &mut Map<String, Value>) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    let colors = match obj.get_mut("color") {
+        Some(Value::Object(colors)) => colors,
+        _ => return diagnostics,
+    };
+    for (name, color) in colors.iter_mut() {
+        let shades = match color {
+            Value::Object(shades) => shades,
+            _ => continue,
+        };
+        let base = match shades.remove("generate") {
+            Some(base) => base,
+            None => continue,
+        };
+        match base.as_str().and_then(generate) {
+            Some(mut generated) => {
+                generated.extend(std::mem::take(shades));
+                *shades = generated;
+            }
+            None => {
+                let message = match &base {
+                    Value::String(base) => format!("`{}` is not a hex color", base),
+                    _ => format!("expected a hex color, found {}", describe(&base)),
+                };
+                diagnostics.push(Diagnostic::error(
+                    &format!("color.{}.generate", name),
+                    message,
+                ));
+            }
+        }
+    }
+    diagnostics
+}
@@ -1,87 +1,204 @@
 use crate::config::Config;
+use crate::config::Container;
 use crate::config::FontSize;
+use indexmap::IndexMap;
 use serde_json::{Map, Value};
 use std::collections::HashMap;
 
+use super::error::{Error, Severity};
+use super::filter;
 use super::get_object;
+use super::palette;
 use super::plugin;
-use super::plugin::create_utility;
 use super::plugin::Mode::{OnlyPositive, WithNegative};
+use super::plugin::{create_utility, create_value_utility, ValueUtility};
+use super::schema::{
+    list, scale, section, ContainerOptions, CorePlugins, FontFamily, FontSizeEntry, Padding,
+    UtilityEntry, VariantEntry,
+};
+use super::theme::user_path;
 use super::utility;
+use super::validate::validate;
 
-pub fn parse(source: &str) -> serde_json::Result<Config> {
-    let value: Value = serde_json::from_str(source)?;
-    let obj: Map<String, Value> = value.as_object().unwrap().clone();
-    let font_size = extract_font_size(&obj);
-    let mut break_point = get_object(&obj, "break-before");
-    break_point.extend(get_object(&obj, "break-after"));
-    break_point.extend(get_object(&obj, "break-inside"));
+pub fn parse(source: &str) -> Result<Config, Error> {
+    match serde_json::from_str(source)? {
+        Value::Object(obj) => parse_object(obj),
+        _ => Err(Error::NotAnObject),
+    }
+}
+
+/// Build a config from its JSON object, with generated color palettes
+/// expanded. Fails with every diagnostic when a
+/// section has the wrong shape; warnings are kept in `diagnostics`.
+pub fn parse_object(mut obj: Map<String, Value>) -> Result<Config, Error> {
+    let mut diagnostics = palette::expand(&mut obj);
+    diagnostics.extend(validate(&obj));
+    for diagnostic in &mut diagnostics {
+        diagnostic.path = user_path(&diagnostic.path);
+    }
+    if diagnostics
+        .iter()
+        .any(|diagnostic| diagnostic.severity == Severity::Error)
+    {
+        return Err(Error::Invalid(diagnostics));
+    }
 
-    let (base, spacing) = plugin::extract_base(&obj);
+    let core_plugins: CorePlugins = obj
+        .get("corePlugins")
+        .and_then(|core_plugins| serde::Deserialize::deserialize(core_plugins).ok())
+        .unwrap_or_default();
+    let mut utility = utility::extract(&obj);
+    utility.retain(|name, _| core_plugins.enabled(name));
 
-    let config = Config {
-        base,
+    let (custom_utilities, value_utilities) = extract_utilities(&obj);
+    let font_size = extract_font_size(&obj);
+    let break_point = ["break-before", "break-inside", "break-after"]
+        .iter()
+        .map(|section| (section.to_string(), get_object(&obj, section)))
+        .collect();
+
+    Ok(Config {
+        base: plugin::extract_base(&obj),
         font_size,
         break_point,
-        spacing,
-        utility: utility::extract(&obj),
+        screens: scale(&obj, "screens"),
+        container: extract_container(&obj),
+        spacing: scale(&obj, "spacing"),
+        utility,
         font_weight: extract_hash_map(&obj, "font_weight"),
+        font_family: section::<FontFamily>(&obj, "fontFamily")
+            .into_iter()
+            .map(|(key, fonts)| (key, fonts.to_string()))
+            .collect(),
         line_height: extract_hash_map(&obj, "lineHeight"),
-        color: get_object(&obj, "color"),
+        color: section(&obj, "color"),
+        background_image: extract_hash_map(&obj, "backgroundImage"),
+        background_size: extract_hash_map(&obj, "backgroundSize"),
+        background_position: extract_hash_map(&obj, "backgroundPosition"),
+        gradient_color_stop_positions: extract_hash_map(&obj, "gradientColorStopPositions"),
+        stroke_width: extract_hash_map(&obj, "strokeWidth"),
+        opacity: extract_hash_map(&obj, "opacity"),
+        box_shadow: extract_hash_map(&obj, "boxShadow"),
+        list_style_type: extract_hash_map(&obj, "listStyleType"),
+        list_style_image: extract_hash_map(&obj, "listStyleImage"),
+        outline_width: extract_hash_map(&obj, "outlineWidth"),
+        outline_offset: extract_hash_map(&obj, "outlineOffset"),
+        transition_duration: extract_hash_map(&obj, "transitionDuration"),
         aspect_ratio: extract_hash_map(&obj, "aspectRatio"),
         float: get_object(&obj, "float"),
-        plugins: vec![
-            create_utility("margin", &obj, WithNegative).unwrap_or_default(),
-            create_utility("padding", &obj, OnlyPositive).unwrap_or_default(),
-            create_utility("width", &obj, OnlyPositive).unwrap_or_default(),
-            create_utility("height", &obj, OnlyPositive).unwrap_or_default(),
-            create_utility("inset", &obj, WithNegative).unwrap_or_default(),
-            create_utility("z_index", &obj, WithNegative).unwrap_or_default(),
-            create_utility("basis", &obj, OnlyPositive).unwrap_or_default(),
-            create_utility("columns", &obj, OnlyPositive).unwrap_or_default(),
-            create_utility("flex", &obj, OnlyPositive).unwrap_or_default(),
-        ],
-    };
-
-    Ok(config)
+        filter: filter::extract(&obj),
+        variants: extract_variants(&obj),
+        prefix: extract_string(&obj, "prefix").unwrap_or_default(),
+        separator: extract_string(&obj, "separator")
+            .filter(|separator| !separator.is_empty())
+            .unwrap_or_else(|| ":".to_string()),
+        safelist: list(&obj, "safelist"),
+        blocklist: list(&obj, "blocklist"),
+        plugins: [
+            ("margin", WithNegative),
+            ("padding", OnlyPositive),
+            ("width", OnlyPositive),
+            ("height", OnlyPositive),
+            ("inset", WithNegative),
+            ("z_index", WithNegative),
+            ("basis", OnlyPositive),
+            ("columns", OnlyPositive),
+            ("flex", OnlyPositive),
+        ]
+        .iter()
+        .filter(|(name, _)| core_plugins.enabled(name))
+        .map(|(name, mode)| {
+            let plugin = create_utility(name, &obj, *mode).unwrap_or_default();
+            (name.to_string(), plugin)
+        })
+        .collect(),
+        components: Map::new(),
+        custom_utilities,
+        value_utilities,
+        preflight: core_plugins.enabled("preflight"),
+        core_plugins,
+        css_variables: false,
+        theme: obj,
+        diagnostics,
+    })
 }
 
 fn extract_font_size(value: &Map<String, Value>) -> HashMap<String, FontSize> {
-    let font_size: Map<String, Value> =
-        value.get("font_size").unwrap().as_object().unwrap().clone();
-    let mut result = HashMap::new();
-    for (item, val) in font_size.iter() {
-        let line_height = val.as_array().unwrap()[1]
-            .as_object()
-            .unwrap()
-            .get("lineHeight")
-            .unwrap();
-        if line_height.is_number() {
-            let font = FontSize {
-                value: val.as_array().unwrap()[0].as_str().unwrap().to_string(),
-                line_height: line_height.as_i64().unwrap().to_string(),
-            };
-            result.insert(item.to_string(), font);
-        } else if line_height.is_string() {
-            let font = FontSize {
-                value: val.as_array().unwrap()[0].as_str().unwrap().to_string(),
-                line_height: line_height.as_str().unwrap().to_string(),
+    section(value, "font_size")
+        .into_iter()
+        .map(|(key, entry)| {
+            let font = match entry {
+                FontSizeEntry::Size(value) => FontSize {
+                    value,
+                    line_height: None,
+                },
+                FontSizeEntry::WithLineHeight(value, line_height) => FontSize {
+                    value,
+                    line_height: Some(line_height.to_string()),
+                },
+                FontSizeEntry::WithOptions(value, options) => FontSize {
+                    value,
+                    line_height: options
+                        .line_height
+                        .map(|line_height| line_height.to_string()),
+                },
             };
-            result.insert(item.to_string(), font);
-        }
+            (key, font)
+        })
+        .collect()
+}
+
+fn extract_container(value: &Map<String, Value>) -> Container {
+    let container: ContainerOptions = value
+        .get("container")
+        .and_then(|container| serde::Deserialize::deserialize(container).ok())
+        .unwrap_or_default();
+    let padding = match container.padding {
+        Padding::All(padding) => HashMap::from([("DEFAULT".to_string(), padding)]),
+        Padding::Screens(padding) => padding.into_iter().collect(),
+    };
+    Container {
+        center: container.center,
+        padding,
     }
-    result
+}
+
+fn extract_string(value: &Map<String, Value>, key: &str) -> Option<String> {
+    Some(value.get(key)?.as_str()?.to_string())
 }
 
 fn extract_hash_map(value: &Map<String, Value>, key: &str) -> HashMap<String, String> {
-    if value.get(key).is_none() || value.get(key).unwrap().as_object().is_none() {
-        return HashMap::new();
+    scale(value, key).into_iter().collect()
+}
+
+fn extract_variants(value: &Map<String, Value>) -> IndexMap<String, Vec<String>> {
+    let mut variants: IndexMap<String, Vec<String>> = section::<VariantEntry>(value, "variants")
+        .into_iter()
+        .map(|(name, variant)| (name, variant.into_vec()))
+        .collect();
+    for (screen, size) in scale(value, "screens") {
+        variants.insert(screen, vec![format!("@media (min-width: {})", size)]);
     }
+    variants
+}
 
-    let font_weight: Map<String, Value> = value.get(key).unwrap().as_object().unwrap().clone();
-    let mut result = HashMap::new();
-    for (key, val) in font_weight.iter() {
-        result.insert(key.to_string(), val.as_str().unwrap().to_string());
+// `utilities` of the config: single classes join the ones registered by
+// the input stylesheet, families are resolved against their scale.
+fn extract_utilities(
+    obj: &Map<String, Value>,
+) -> (Map<String, Value>, IndexMap<String, ValueUtility>) {
+    let mut classes = Map::new();
+    let mut families = IndexMap::new();
+    for (name, entry) in section::<UtilityEntry>(obj, "utilities") {
+        let name = name.trim_start_matches('.');
+        match entry {
+            UtilityEntry::Static(style) => {
+                classes.insert(format!(".{}", name), Value::Object(style));
+            }
+            UtilityEntry::Values(options) => {
+                families.insert(name.to_string(), create_value_utility(options, obj));
+            }
+        }
     }
-    result
+    (classes, families)
 }
@@ -1,23 +1,137 @@
 use super::get_object;
+use super::schema::{section, PluginEntry, Properties, UtilityOptions, UtilityValues};
+use super::theme::section_name;
 use super::Object;
+use indexmap::IndexMap;
 use serde_json::Map;
 use serde_json::Value;
-use std::collections::HashMap;
 
 // name: key -> [properties]
 // value: inset -> [[String("top"), String("right"), String("bottom"), String("left")]]
 // css: .inset-0 { top: 0px; right: 0px; bottom: 0px; left: 0px; }
 pub type Utility = Map<String, Value>;
 
-#[derive(Clone, Copy)]
+// Plugins in the order their rules are emitted, following Tailwind's core
+// plugin order so that later utilities win over the ones they refine. These
+// are also the names `corePlugins` toggles.
+pub const CORE_PLUGINS: [&str; 76] = [
+    "container",
+    "accessibility",
+    "pointer-events",
+    "visibility",
+    "position",
+    "inset",
+    "isolation",
+    "z_index",
+    "float",
+    "clear",
+    "margin",
+    "box-sizing",
+    "display",
+    "aspect-ratio",
+    "height",
+    "width",
+    "flex",
+    "basis",
+    "table-layout",
+    "caption-side",
+    "border-collapse",
+    "border-spacing",
+    "cursor",
+    "touch-action",
+    "user-select",
+    "resize",
+    "scroll-snap-type",
+    "scroll-snap-align",
+    "scroll-snap-stop",
+    "list-style-position",
+    "list-style-type",
+    "list-style-image",
+    "appearance",
+    "columns",
+    "break-before",
+    "break-inside",
+    "break-after",
+    "flex-direction",
+    "flex-wrap",
+    "overflow",
+    "overscroll_behavior",
+    "scroll-behavior",
+    "background-color",
+    "background-image",
+    "gradient-color-stops",
+    "box-decoration-break",
+    "background-size",
+    "background-attachment",
+    "background-clip",
+    "background-position",
+    "background-repeat",
+    "background-origin",
+    "fill",
+    "stroke",
+    "stroke-width",
+    "object_fit",
+    "padding",
+    "font_family",
+    "font_size",
+    "font_weight",
+    "line_height",
+    "caret-color",
+    "accent-color",
+    "opacity",
+    "background-blend-mode",
+    "mix-blend-mode",
+    "box-shadow",
+    "outline-style",
+    "outline-width",
+    "outline-offset",
+    "outline-color",
+    "filter",
+    "backdrop-filter",
+    "transition-duration",
+    "will-change",
+    "forced-color-adjust",
+];
+
+// Tailwind's plugin names for generators named differently here.
+const PLUGIN_ALIASES: [(&str, &str); 1] = [("flex-basis", "basis")];
+
+/// Name of a plugin as `corePlugins` compares it, so that `zIndex`,
+/// `z-index` and `z_index` are the same plugin, and Tailwind's `flexBasis`
+/// is `basis`.
+pub fn plugin_key(name: &str) -> String {
+    let mut key = String::with_capacity(name.len());
+    for c in name.chars() {
+        match c {
+            '_' => key.push('-'),
+            c if c.is_ascii_uppercase() => {
+                key.push('-');
+                key.push(c.to_ascii_lowercase());
+            }
+            c => key.push(c),
+        }
+    }
+    match PLUGIN_ALIASES.iter().find(|(alias, _)| *alias == key) {
+        Some((_, plugin)) => plugin.to_string(),
+        None => key,
+    }
+}
+
+/// Whether `corePlugins` can toggle `name`. Other names, like the classes
+/// registered by the input stylesheet, are always enabled.
+pub fn is_core_plugin(name: &str) -> bool {
+    let key = plugin_key(name);
+    key == "preflight" || CORE_PLUGINS.iter().any(|plugin| plugin_key(plugin) == key)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Mode {
     WithNegative,
     OnlyPositive,
 }
 
-fn build_value(data: &mut Utility, item: &Value, mode: Mode) -> Option<()> {
-    let key = item.get(0)?.as_str()?.to_string();
-    let variants = item.get(1)?.clone();
+fn build_value(data: &mut Utility, key: String, properties: Vec<String>, mode: Mode) {
+    let variants = Value::from(properties);
     match mode {
         Mode::WithNegative => {
             data.insert(key.to_string(), variants.clone());
@@ -27,7 +141,6 @@ fn build_value(data: &mut Utility, item: &Value, mode: Mode) -> Option<()> {
             data.insert(key, variants);
         }
     }
-    Some(())
 }
 
 pub fn create_utility<'a>(
@@ -35,27 +148,99 @@ pub fn create_utility<'a>(
     obj: &'a Map<String, Value>,
     mode: Mode,
 ) -> Option<Utility> {
-    let plugin = obj.get("plugins")?.as_object()?;
-    let arr = plugin.get(name)?.as_array()?;
+    let entries = section::<Vec<PluginEntry>>(obj, "plugins").shift_remove(name)?;
     let mut data: Utility = Map::new();
 
-    for item in arr {
-        if item.get(0)?.is_string() {
-            build_value(&mut data, item, mode);
-            continue;
+    for entry in entries {
+        match entry {
+            PluginEntry::Single(key, properties) => build_value(&mut data, key, properties, mode),
+            PluginEntry::Group(group) => {
+                for (key, properties) in group {
+                    build_value(&mut data, key, properties, mode);
+                }
+            }
         }
+    }
+
+    Some(data)
+}
 
-        for deep_item in item.as_array()? {
-            build_value(&mut data, deep_item, mode);
+/// A family of classes declared in `utilities`, e.g. `tab-4` for
+/// `tab-size: 4`.
+#[derive(Debug, Clone)]
+pub struct ValueUtility {
+    pub properties: Vec<String>,
+    pub values: IndexMap<String, String>,
+    pub mode: Mode,
+}
+
+// Flatten nested scales such as color palettes into `red-500` keys, with
+// `DEFAULT` standing for the parent key. Font sizes keep their size.
+fn flatten(key: &str, value: &Value, values: &mut IndexMap<String, String>) {
+    match value {
+        Value::String(value) => {
+            values.insert(key.to_string(), value.clone());
+        }
+        Value::Number(value) => {
+            values.insert(key.to_string(), value.to_string());
+        }
+        Value::Array(items) => {
+            if let Some(first) = items.first().and_then(Value::as_str) {
+                values.insert(key.to_string(), first.to_string());
+            }
         }
+        Value::Object(obj) => {
+            for (child, value) in obj {
+                let key = match (key, child.as_str()) {
+                    ("", _) => child.clone(),
+                    (_, "DEFAULT") => key.to_string(),
+                    _ => format!("{}-{}", key, child),
+                };
+                flatten(&key, value, values);
+            }
+        }
+        _ => {}
     }
+}
 
-    Some(data)
+/// The theme value at a path such as `theme.tabSize` or `colors.blue`,
+/// the `theme.` prefix being optional.
+pub fn theme_section<'a>(obj: &'a Map<String, Value>, path: &str) -> Option<&'a Value> {
+    let mut segments = path.strip_prefix("theme.").unwrap_or(path).split('.');
+    let section = obj.get(section_name(segments.next()?))?;
+    segments.try_fold(section, |value, segment| value.get(segment))
+}
+
+pub fn create_value_utility(options: UtilityOptions, obj: &Map<String, Value>) -> ValueUtility {
+    let mut values = IndexMap::new();
+    match options.values {
+        UtilityValues::Theme(path) => {
+            if let Some(section) = theme_section(obj, &path) {
+                flatten("", section, &mut values);
+            }
+        }
+        UtilityValues::Scale(scale) => {
+            for (key, value) in scale {
+                values.insert(key, value.to_string());
+            }
+        }
+    }
+    ValueUtility {
+        properties: match options.property {
+            Properties::Single(property) => vec![property],
+            Properties::List(properties) => properties,
+        },
+        values,
+        mode: match options.supports_negative {
+            true => Mode::WithNegative,
+            false => Mode::OnlyPositive,
+        },
+    }
 }
 
-pub fn extract_base(obj: &'_ Map<String, Value>) -> (Object, Map<String, Value>) {
+pub fn extract_base(obj: &'_ Map<String, Value>) -> Object {
     let spacing = get_object(obj, "spacing");
-    let mut base: Object = HashMap::new();
+    let mut base = Object::new();
 
     base.insert("basis".into(), {
         let mut data = get_object(obj, "data");
@@ -87,5 +272,5 @@ pub fn extract_base(obj: &'_ Map<String, Value>) -> (Object, Map<String, Value>)
         data
     });
 
-    (base, spacing)
+    base
 }
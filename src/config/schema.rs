@@ -0,0 +1,289 @@
+use indexmap::IndexMap;
+use regex::Regex;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::{Map, Number, Value};
+use std::fmt;
+
+use super::plugin::{is_core_plugin, plugin_key};
+
+/// A theme value written as a string or a bare number, e.g. `"0.25rem"` or
+/// `1.5`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum Scalar {
+    String(String),
+    Number(Number),
+}
+
+impl fmt::Display for Scalar {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Scalar::String(value) => write!(f, "{}", value),
+            Scalar::Number(value) => write!(f, "{}", value),
+        }
+    }
+}
+
+/// An entry of `colors`: a single color or a palette of shades.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum Color {
+    Single(String),
+    Palette(IndexMap<String, String>),
+}
+
+/// An entry of `fontSize`: a size, `[size, lineHeight]` or
+/// `[size, { lineHeight }]`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum FontSizeEntry {
+    Size(String),
+    WithLineHeight(String, Scalar),
+    WithOptions(String, FontSizeOptions),
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FontSizeOptions {
+    pub line_height: Option<Scalar>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ContainerOptions {
+    #[serde(default)]
+    pub center: bool,
+    #[serde(default)]
+    pub padding: Padding,
+}
+
+/// Horizontal padding of the container, for every screen or per screen.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum Padding {
+    All(String),
+    Screens(IndexMap<String, String>),
+}
+
+impl Default for Padding {
+    fn default() -> Self {
+        Padding::Screens(IndexMap::new())
+    }
+}
+
+/// An entry of `fontFamily`: a font or a stack of fonts, most preferred
+/// first.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum FontFamily {
+    Single(String),
+    Stack(Vec<String>),
+}
+
+impl fmt::Display for FontFamily {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FontFamily::Single(font) => write!(f, "{}", font),
+            FontFamily::Stack(fonts) => write!(f, "{}", fonts.join(", ")),
+        }
+    }
+}
+
+/// An entry of a filter scale. Drop shadows may stack several shadows.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum FilterValue {
+    Single(Scalar),
+    List(Vec<String>),
+}
+
+/// An entry of a plugin: `[class, [properties]]`, or a group of them.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum PluginEntry {
+    Single(String, Vec<String>),
+    Group(Vec<(String, Vec<String>)>),
+}
+
+/// An entry of `variants`: a selector with `&` or an at-rule, or several
+/// selectors of which any can match, e.g. `["&:hover", "&:focus"]`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum VariantEntry {
+    Single(String),
+    Any(Vec<String>),
+}
+
+impl VariantEntry {
+    pub fn into_vec(self) -> Vec<String> {
+        match self {
+            VariantEntry::Single(variant) => vec![variant],
+            VariantEntry::Any(variants) => variants,
+        }
+    }
+}
+
+/// An entry of `utilities`: a family of classes that take their value from
+/// a scale, such as `tab-4`, or a single class and its style object.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum UtilityEntry {
+    Values(UtilityOptions),
+    Static(Map<String, Value>),
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct UtilityOptions {
+    pub property: Properties,
+    pub values: UtilityValues,
+    #[serde(default)]
+    pub supports_negative: bool,
+}
+
+/// The properties a utility sets, all to the same value.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum Properties {
+    Single(String),
+    List(Vec<String>),
+}
+
+/// The scale of a utility: a theme path such as `theme.tabSize`, or the
+/// scale itself.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum UtilityValues {
+    Theme(String),
+    Scale(IndexMap<String, Scalar>),
+}
+
+/// `corePlugins`: the only plugins to keep, or toggles by plugin name.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum CorePlugins {
+    Only(Vec<String>),
+    Toggles(IndexMap<String, bool>),
+}
+
+impl Default for CorePlugins {
+    fn default() -> Self {
+        CorePlugins::Toggles(IndexMap::new())
+    }
+}
+
+impl CorePlugins {
+    pub fn enabled(&self, name: &str) -> bool {
+        if !is_core_plugin(name) {
+            return true;
+        }
+        let key = plugin_key(name);
+        match self {
+            CorePlugins::Only(names) => names.iter().any(|name| plugin_key(name) == key),
+            CorePlugins::Toggles(toggles) => toggles
+                .iter()
+                .find(|(name, _)| plugin_key(name) == key)
+                .is_none_or(|(_, enabled)| *enabled),
+        }
+    }
+}
+
+/// A class name, or a regular expression between slashes such as
+/// `/bg-(red|green)-500/`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(try_from = "String")]
+pub enum ClassPattern {
+    Class(String),
+    Regex(Regex),
+}
+
+impl TryFrom<String> for ClassPattern {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, String> {
+        match value.len() > 1 && value.starts_with('/') && value.ends_with('/') {
+            true => Ok(ClassPattern::Regex(compile(&value)?)),
+            false => Ok(ClassPattern::Class(value)),
+        }
+    }
+}
+
+impl ClassPattern {
+    pub fn matches(&self, class: &str) -> bool {
+        match self {
+            ClassPattern::Class(name) => name == class,
+            ClassPattern::Regex(regex) => regex.is_match(class),
+        }
+    }
+}
+
+/// An entry of `safelist`: a class or pattern, or a pattern whose matches
+/// are also generated with the given variants.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum SafelistEntry {
+    Class(ClassPattern),
+    Pattern {
+        #[serde(deserialize_with = "deserialize_regex")]
+        pattern: Regex,
+        #[serde(default)]
+        variants: Vec<String>,
+    },
+}
+
+/// Compile a pattern, written with or without the surrounding slashes. The
+/// error is condensed to one line.
+pub fn compile(pattern: &str) -> Result<Regex, String> {
+    let pattern = pattern
+        .strip_prefix('/')
+        .and_then(|pattern| pattern.strip_suffix('/'))
+        .unwrap_or(pattern);
+    Regex::new(pattern).map_err(|e| {
+        let message = e.to_string();
+        let reason = message.lines().last().unwrap_or_default();
+        format!(
+            "invalid pattern `{}`: {}",
+            pattern,
+            reason.trim_start_matches("error: ")
+        )
+    })
+}
+
+fn deserialize_regex<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Regex, D::Error> {
+    let pattern = String::deserialize(deserializer)?;
+    compile(&pattern).map_err(serde::de::Error::custom)
+}
+
+/// Whether `value` has the shape of `T`.
+pub fn is<T: DeserializeOwned>(value: &Value) -> bool {
+    T::deserialize(value).is_ok()
+}
+
+/// The entries of section `key` that deserialize to `T`. The others are
+/// reported by `validate` and left out.
+pub fn section<T: DeserializeOwned>(obj: &Map<String, Value>, key: &str) -> IndexMap<String, T> {
+    obj.get(key)
+        .and_then(Value::as_object)
+        .into_iter()
+        .flatten()
+        .filter_map(|(key, value)| Some((key.clone(), T::deserialize(value).ok()?)))
+        .collect()
+}
+
+/// The items of list `key` that deserialize to `T`.
+pub fn list<T: DeserializeOwned>(obj: &Map<String, Value>, key: &str) -> Vec<T> {
+    obj.get(key)
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(|value| T::deserialize(value).ok())
+        .collect()
+}
+
+/// The entries of section `key` as strings, for scales such as `opacity`.
+pub fn scale(obj: &Map<String, Value>, key: &str) -> IndexMap<String, String> {
+    section::<Scalar>(obj, key)
+        .into_iter()
+        .map(|(key, value)| (key, value.to_string()))
+        .collect()
+}
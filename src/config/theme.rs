@@ -0,0 +1,134 @@
+use serde_json::{Map, Value};
+
+// Tailwind's theme key -> section of the config holding it, where the names
+// differ.
+const ALIASES: [(&str, &str); 4] = [
+    ("colors", "color"),
+    ("fontSize", "font_size"),
+    ("fontWeight", "font_weight"),
+    ("zIndex", "z_index"),
+];
+
+/// Section of the config holding a Tailwind theme key.
+pub fn section_name(key: &str) -> &str {
+    ALIASES
+        .iter()
+        .find(|(alias, _)| *alias == key)
+        .map_or(key, |(_, section)| section)
+}
+
+/// Tailwind's theme key of a config section, as users write it.
+pub fn theme_key(section: &str) -> &str {
+    ALIASES
+        .iter()
+        .find(|(_, name)| *name == section)
+        .map_or(section, |(alias, _)| alias)
+}
+
+/// A diagnostic path such as `font_size.huge` with its section named the
+/// way users write it, `fontSize.huge`.
+pub fn user_path(path: &str) -> String {
+    let end = path.find(['.', '[']).unwrap_or(path.len());
+    format!("{}{}", theme_key(&path[..end]), &path[end..])
+}
+
+// `colors.blue.500` or `spacing[2.5]` -> its keys.
+fn split_path(path: &str) -> Vec<String> {
+    let mut segments = Vec::new();
+    let mut segment = String::new();
+    let mut chars = path.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '.' | '[' => {
+                if !segment.is_empty() {
+                    segments.push(std::mem::take(&mut segment));
+                }
+                if c == '[' {
+                    segments.push(chars.by_ref().take_while(|c| *c != ']').collect());
+                }
+            }
+            _ => segment.push(c),
+        }
+    }
+    if !segment.is_empty() {
+        segments.push(segment);
+    }
+    segments
+}
+
+fn keys(obj: &Map<String, Value>) -> String {
+    obj.keys()
+        .map(|key| format!("'{}'", key))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Resolve a dotted theme path such as `colors.blue.500` to its CSS value.
+/// The error says which part of the path is wrong and what would fit.
+pub fn lookup(theme: &Map<String, Value>, path: &str) -> Result<String, String> {
+    let mut segments = split_path(path);
+    if let Some(first) = segments.first_mut() {
+        *first = section_name(first).to_string();
+    }
+
+    let mut value = None;
+    let mut obj = theme;
+    let mut parent = String::new();
+    let mut rest = &segments[..];
+    while !rest.is_empty() {
+        // Keys may contain dots themselves, like `spacing.0.5`.
+        let found = (1..=rest.len())
+            .rev()
+            .find_map(|n| Some((n, obj.get(&rest[..n].join("."))?)));
+        let (n, next) = match found {
+            Some(found) => found,
+            None if parent.is_empty() => {
+                return Err(format!("'{}' does not exist in your theme config", path))
+            }
+            None => {
+                return Err(format!(
+                "'{}' does not exist in your theme config. '{}' has the following valid keys: {}",
+                path,
+                parent,
+                keys(obj)
+            ))
+            }
+        };
+        parent = rest[n - 1].clone();
+        rest = &rest[n..];
+        value = Some(next);
+        match next {
+            Value::Object(next) => obj = next,
+            _ if !rest.is_empty() => {
+                return Err(format!(
+                    "'{}' does not exist in your theme config. '{}' is not an object",
+                    path, parent
+                ))
+            }
+            _ => {}
+        }
+    }
+
+    match value {
+        Some(Value::Object(obj)) => match obj.get("DEFAULT") {
+            Some(Value::String(value)) => Ok(value.clone()),
+            _ => Err(format!(
+                "'{}' was found but does not resolve to a value. Use one of its keys: {}",
+                path,
+                keys(obj)
+            )),
+        },
+        Some(Value::String(value)) => Ok(value.clone()),
+        Some(Value::Number(value)) => Ok(value.to_string()),
+        // Font families are lists, font sizes are `[size, options]`.
+        Some(Value::Array(values)) => {
+            let values: Vec<&str> = values.iter().filter_map(Value::as_str).collect();
+            match values.first() {
+                Some(_) if values.len() > 1 => Ok(values.join(", ")),
+                Some(value) => Ok(value.to_string()),
+                None => Err(format!("'{}' does not resolve to a value", path)),
+            }
+        }
+        _ => Err(format!("'{}' does not resolve to a value", path)),
+    }
+}
@@ -0,0 +1,389 @@
+use indexmap::IndexMap;
+use serde_json::{Map, Value};
+
+// Token type -> theme section it is imported into, in the order the
+// sections are written.
+const SECTIONS: [(&str, &str); 7] = [
+    ("color", "colors"),
+    ("dimension", "spacing"),
+    ("dimension", "fontSize"),
+    ("fontFamily", "fontFamily"),
+    ("fontWeight", "fontWeight"),
+    ("shadow", "boxShadow"),
+    ("duration", "transitionDuration"),
+];
+
+// Leading groups that only name the category of a token, e.g. `color` in
+// `color.blue.500`. They are left out of the theme key.
+const CATEGORIES: [(&str, &[&str]); 7] = [
+    ("colors", &["color", "colors"]),
+    (
+        "spacing",
+        &[
+            "spacing",
+            "space",
+            "size",
+            "sizes",
+            "dimension",
+            "dimensions",
+        ],
+    ),
+    (
+        "fontSize",
+        &["font", "fonts", "fontsize", "fontsizes", "size", "sizes"],
+    ),
+    (
+        "fontFamily",
+        &[
+            "font",
+            "fonts",
+            "fontfamily",
+            "fontfamilies",
+            "family",
+            "families",
+        ],
+    ),
+    (
+        "fontWeight",
+        &[
+            "font",
+            "fonts",
+            "fontweight",
+            "fontweights",
+            "weight",
+            "weights",
+        ],
+    ),
+    (
+        "boxShadow",
+        &["shadow", "shadows", "boxshadow", "boxshadows", "elevation"],
+    ),
+    (
+        "transitionDuration",
+        &[
+            "duration",
+            "durations",
+            "motion",
+            "transition",
+            "transitions",
+            "time",
+        ],
+    ),
+];
+
+// Font weight names of the DTCG format.
+const FONT_WEIGHTS: [(&str, u16); 18] = [
+    ("thin", 100),
+    ("hairline", 100),
+    ("extra-light", 200),
+    ("ultra-light", 200),
+    ("light", 300),
+    ("normal", 400),
+    ("regular", 400),
+    ("book", 400),
+    ("medium", 500),
+    ("semi-bold", 600),
+    ("demi-bold", 600),
+    ("bold", 700),
+    ("extra-bold", 800),
+    ("ultra-bold", 800),
+    ("black", 900),
+    ("heavy", 900),
+    ("extra-black", 950),
+    ("ultra-black", 950),
+];
+
+struct Token {
+    kind: Option<String>,
+    value: Value,
+}
+
+fn normalize(segment: &str) -> String {
+    segment
+        .chars()
+        .filter(|c| *c != '-' && *c != '_')
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+// Every token of a group by dotted path, with the `$type` inherited from
+// its groups.
+fn collect(
+    group: &Map<String, Value>,
+    path: &str,
+    kind: Option<&str>,
+    tokens: &mut IndexMap<String, Token>,
+) {
+    let kind = group.get("$type").and_then(Value::as_str).or(kind);
+    for (name, child) in group {
+        let child = match child {
+            Value::Object(child) if !name.starts_with('$') => child,
+            _ => continue,
+        };
+        let path = match path {
+            "" => name.clone(),
+            _ => format!("{}.{}", path, name),
+        };
+        match child.get("$value") {
+            Some(value) => {
+                let kind = child.get("$type").and_then(Value::as_str).or(kind);
+                let token = Token {
+                    kind: kind.map(str::to_string),
+                    value: value.clone(),
+                };
+                tokens.insert(path, token);
+            }
+            None => collect(child, &path, kind, tokens),
+        }
+    }
+}
+
+// `color.blue.500` of an alias `{color.blue.500}`.
+fn alias(value: &Value) -> Option<&str> {
+    value.as_str()?.strip_prefix('{')?.strip_suffix('}')
+}
+
+// `value` with its aliases replaced by the values they point to, along with
+// the type of the token an alias token points to.
+fn resolve(
+    tokens: &IndexMap<String, Token>,
+    value: &Value,
+    resolving: &mut Vec<String>,
+) -> Result<(Value, Option<String>), String> {
+    if let Some(target) = alias(value) {
+        if resolving.iter().any(|path| path == target) {
+            return Err(format!("`{{{}}}` is part of an alias cycle", target));
+        }
+        let token = tokens
+            .get(target)
+            .ok_or_else(|| format!("`{{{}}}` doesn't refer to a token", target))?;
+        resolving.push(target.to_string());
+        let (value, kind) = resolve(tokens, &token.value, resolving)?;
+        resolving.pop();
+        return Ok((value, token.kind.clone().or(kind)));
+    }
+    let value = match value {
+        Value::Array(items) => Value::Array(
+            items
+                .iter()
+                .map(|item| Ok(resolve(tokens, item, resolving)?.0))
+                .collect::<Result<_, String>>()?,
+        ),
+        Value::Object(obj) => Value::Object(
+            obj.iter()
+                .map(|(key, item)| Ok((key.clone(), resolve(tokens, item, resolving)?.0)))
+                .collect::<Result<_, String>>()?,
+        ),
+        value => value.clone(),
+    };
+    Ok((value, None))
+}
+
+fn number(value: &Value) -> Option<String> {
+    match value {
+        Value::Number(number) => Some(number.to_string()),
+        Value::String(number) => Some(number.clone()),
+        _ => None,
+    }
+}
+
+// A `dimension` or `duration`: `"16px"` or `{ "value": 16, "unit": "px" }`.
+fn measure(value: &Value) -> Option<String> {
+    match value {
+        Value::String(value) => Some(value.clone()),
+        Value::Object(obj) => Some(format!(
+            "{}{}",
+            number(obj.get("value")?)?,
+            obj.get("unit")?.as_str()?
+        )),
+        _ => None,
+    }
+}
+
+// A `color`: a CSS color string, or `{ colorSpace, components, alpha, hex }`.
+fn color(value: &Value) -> Option<String> {
+    let obj = match value {
+        Value::String(color) => return Some(color.clone()),
+        Value::Object(obj) => obj,
+        _ => return None,
+    };
+    let alpha = obj.get("alpha").and_then(Value::as_f64).unwrap_or(1.0);
+    if let (Some(hex), true) = (obj.get("hex").and_then(Value::as_str), alpha >= 1.0) {
+        return Some(hex.to_string());
+    }
+    let space = obj.get("colorSpace")?.as_str()?;
+    let components = obj.get("components")?.as_array()?;
+    let channels: Vec<String> = match space {
+        "srgb" => components
+            .iter()
+            .map(|c| match c.as_f64() {
+                Some(c) => Some(((c * 255.0).round() as i64).to_string()),
+                None => c.as_str().map(str::to_string),
+            })
+            .collect::<Option<_>>()?,
+        "hsl" | "hwb" => components
+            .iter()
+            .enumerate()
+            .map(|(i, c)| match i {
+                0 => number(c),
+                _ => number(c).map(|c| format!("{}%", c)),
+            })
+            .collect::<Option<_>>()?,
+        _ => components.iter().map(number).collect::<Option<_>>()?,
+    };
+    let channels = match alpha < 1.0 {
+        true => format!("{} / {}", channels.join(" "), alpha),
+        false => channels.join(" "),
+    };
+    Some(match space {
+        "srgb" => format!("rgb({})", channels),
+        "hsl" | "hwb" | "lab" | "lch" | "oklab" | "oklch" => format!("{}({})", space, channels),
+        _ => format!("color({} {})", space, channels),
+    })
+}
+
+// A `fontFamily`: a font or a stack, with names that have spaces quoted.
+fn font_family(value: &Value) -> Option<Value> {
+    let quote = |font: &str| match font.contains(' ') && !font.starts_with(['"', '\'']) {
+        true => Value::String(format!("\"{}\"", font)),
+        false => Value::String(font.to_string()),
+    };
+    match value {
+        Value::String(font) => Some(quote(font)),
+        Value::Array(fonts) => Some(Value::Array(
+            fonts
+                .iter()
+                .map(|font| Some(quote(font.as_str()?)))
+                .collect::<Option<_>>()?,
+        )),
+        _ => None,
+    }
+}
+
+fn font_weight(value: &Value) -> Option<String> {
+    match value {
+        Value::Number(weight) => Some(weight.to_string()),
+        Value::String(name) => FONT_WEIGHTS
+            .iter()
+            .find(|(weight, _)| normalize(weight) == normalize(name))
+            .map(|(_, weight)| weight.to_string()),
+        _ => None,
+    }
+}
+
+// A `shadow`: one `{ color, offsetX, offsetY, blur, spread, inset }` or a
+// list of them.
+fn shadow(value: &Value) -> Option<String> {
+    let layer = |value: &Value| {
+        let obj = value.as_object()?;
+        let mut parts = Vec::new();
+        if obj.get("inset").and_then(Value::as_bool) == Some(true) {
+            parts.push("inset".to_string());
+        }
+        for key in ["offsetX", "offsetY", "blur", "spread"] {
+            parts.push(obj.get(key).map_or(Some("0".to_string()), measure)?);
+        }
+        parts.push(color(obj.get("color")?)?);
+        Some(parts.join(" "))
+    };
+    match value {
+        Value::Array(layers) => Some(
+            layers
+                .iter()
+                .map(layer)
+                .collect::<Option<Vec<_>>>()?
+                .join(", "),
+        ),
+        value => layer(value),
+    }
+}
+
+// Theme section and key of a token: the path without the groups naming its
+// category. Dimensions under a `fontSize` or `font.size` group are font
+// sizes, the others spacing.
+fn place(kind: &str, path: &str) -> Option<(&'static str, Vec<String>)> {
+    let segments: Vec<&str> = path.split('.').collect();
+    let normalized: Vec<String> = segments.iter().map(|segment| normalize(segment)).collect();
+    let font_size = kind == "dimension"
+        && (normalized[0] == "fontsize"
+            || normalized[0] == "fontsizes"
+            || (normalized.len() > 1
+                && normalized[0] == "font"
+                && normalized[1].starts_with("size")));
+    let section = SECTIONS
+        .iter()
+        .filter(|(name, _)| *name == kind)
+        .map(|(_, section)| *section)
+        .find(|section| (*section == "fontSize") == font_size)?;
+    let categories = CATEGORIES
+        .iter()
+        .find(|(name, _)| *name == section)
+        .map_or(&[][..], |(_, categories)| categories);
+
+    let mut start = 0;
+    while start + 1 < segments.len() && categories.contains(&normalized[start].as_str()) {
+        start += 1;
+    }
+    Some((
+        section,
+        segments[start..].iter().map(|s| s.to_string()).collect(),
+    ))
+}
+
+/// Theme sections, as written in a config, with the `color`, `dimension`,
+/// `fontFamily`, `fontWeight`, `shadow` and `duration` tokens of a W3C
+/// design tokens file. Aliases such as `{color.blue.500}` are resolved and
+/// tokens of other types are left out.
+pub fn theme(tokens: &Map<String, Value>) -> Result<Map<String, Value>, String> {
+    let mut all = IndexMap::new();
+    collect(tokens, "", None, &mut all);
+
+    let mut sections: IndexMap<&str, Map<String, Value>> = IndexMap::new();
+    for (path, token) in &all {
+        let (value, target_kind) = resolve(&all, &token.value, &mut vec![path.clone()])
+            .map_err(|e| format!("{}: {}", path, e))?;
+        let kind = match token.kind.clone().or(target_kind) {
+            Some(kind) => kind,
+            None => continue,
+        };
+        let (section, key) = match place(&kind, path) {
+            Some(place) => place,
+            None => continue,
+        };
+        let css = match kind.as_str() {
+            "color" => color(&value).map(Value::String),
+            "dimension" | "duration" => measure(&value).map(Value::String),
+            "fontFamily" => font_family(&value),
+            "fontWeight" => font_weight(&value).map(Value::String),
+            "shadow" => shadow(&value).map(Value::String),
+            _ => None,
+        };
+        let css =
+            css.ok_or_else(|| format!("{}: `{}` is not a valid {} token", path, value, kind))?;
+
+        let entries = sections.entry(section).or_default();
+        match (section, &key[..]) {
+            ("colors", [palette @ .., shade]) if !palette.is_empty() => {
+                let palette = entries
+                    .entry(palette.join("-"))
+                    .or_insert_with(|| Value::Object(Map::new()));
+                if let Value::Object(palette) = palette {
+                    palette.insert(shade.clone(), css);
+                }
+            }
+            _ => {
+                entries.insert(key.join("-"), css);
+            }
+        }
+    }
+
+    Ok(SECTIONS
+        .iter()
+        .filter_map(|(_, section)| {
+            Some((
+                section.to_string(),
+                Value::Object(sections.shift_remove(section)?),
+            ))
+        })
+        .collect())
+}
@@ -1,12 +1,10 @@
-use std::collections::HashMap;
-
 use crate::config::extract_object_ext;
 use serde_json::{Map, Value};
 
 use super::{get_object, Object};
 
 pub fn extract(obj: &'_ Map<String, Value>) -> Object {
-    let mut utility: HashMap<String, Map<String, Value>> = HashMap::new();
+    let mut utility = Object::new();
     utility.insert("flex".into(), get_object(obj, "flex"));
     utility.insert("flex-direction".into(), get_object(obj, "flex-direction"));
     utility.insert("flex-wrap".into(), get_object(obj, "flex-wrap"));
@@ -25,6 +23,60 @@ pub fn extract(obj: &'_ Map<String, Value>) -> Object {
         "box-decoration-break".into(),
         get_object(obj, "box-decoration-break"),
     );
+    utility.insert(
+        "background-attachment".into(),
+        get_object(obj, "background-attachment"),
+    );
+    utility.insert("background-clip".into(), get_object(obj, "background-clip"));
+    utility.insert(
+        "background-origin".into(),
+        get_object(obj, "background-origin"),
+    );
+    utility.insert(
+        "background-repeat".into(),
+        get_object(obj, "background-repeat"),
+    );
+    utility.insert("cursor".into(), get_object(obj, "cursor"));
+    utility.insert("pointer-events".into(), get_object(obj, "pointer-events"));
+    utility.insert("user-select".into(), get_object(obj, "user-select"));
+    utility.insert("resize".into(), get_object(obj, "resize"));
+    utility.insert("scroll-behavior".into(), get_object(obj, "scroll-behavior"));
+    utility.insert(
+        "scroll-snap-align".into(),
+        get_object(obj, "scroll-snap-align"),
+    );
+    utility.insert(
+        "scroll-snap-stop".into(),
+        get_object(obj, "scroll-snap-stop"),
+    );
+    utility.insert(
+        "scroll-snap-type".into(),
+        get_object(obj, "scroll-snap-type"),
+    );
+    utility.insert("touch-action".into(), get_object(obj, "touch-action"));
+    utility.insert("accent-color".into(), get_object(obj, "accent-color"));
+    utility.insert("appearance".into(), get_object(obj, "appearance"));
+    utility.insert("will-change".into(), get_object(obj, "will-change"));
+    utility.insert("fill".into(), get_object(obj, "fill"));
+    utility.insert("stroke".into(), get_object(obj, "stroke"));
+    utility.insert("mix-blend-mode".into(), get_object(obj, "mix-blend-mode"));
+    utility.insert(
+        "background-blend-mode".into(),
+        get_object(obj, "background-blend-mode"),
+    );
+    utility.insert("border-collapse".into(), get_object(obj, "border-collapse"));
+    utility.insert("table-layout".into(), get_object(obj, "table-layout"));
+    utility.insert("caption-side".into(), get_object(obj, "caption-side"));
+    utility.insert(
+        "list-style-position".into(),
+        get_object(obj, "list-style-position"),
+    );
+    utility.insert("outline-style".into(), get_object(obj, "outline-style"));
+    utility.insert("accessibility".into(), get_object(obj, "accessibility"));
+    utility.insert(
+        "forced-color-adjust".into(),
+        get_object(obj, "forced-color-adjust"),
+    );
     utility.insert("columns".into(), get_object(obj, "columns"));
     utility.insert("basis".into(), extract_object_ext(obj, "basis", "spacing"));
     utility
@@ -0,0 +1,620 @@
+use indexmap::IndexMap;
+use serde_json::{Map, Value};
+
+use super::error::Diagnostic;
+use super::plugin::{is_core_plugin, theme_section, CORE_PLUGINS};
+use super::schema::{
+    compile, is, ClassPattern, Color, FilterValue, FontFamily, FontSizeEntry, PluginEntry,
+    Properties, Scalar,
+};
+use super::theme::{section_name, theme_key};
+
+// Shape of the entries of a config section.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    // Strings or numbers, e.g. `opacity`.
+    Scale,
+    // CSS lengths, e.g. `spacing`.
+    Length,
+    Color,
+    FontSize,
+    FontFamily,
+    // `.class` -> style object.
+    Class,
+    Variant,
+    Filter,
+    CorePlugin,
+    Plugin,
+    // A single object rather than a map of entries.
+    Container,
+    // A single string option, e.g. `prefix`.
+    Text,
+    // Lists of classes and patterns.
+    Safelist,
+    Blocklist,
+    // Style objects or `{ property, values }` families of classes.
+    Utility,
+}
+
+const SECTIONS: [(&str, Kind); 91] = [
+    ("margin", Kind::Scale),
+    ("spacing", Kind::Length),
+    ("lineHeight", Kind::Scale),
+    ("font_size", Kind::FontSize),
+    ("font_weight", Kind::Scale),
+    ("fontFamily", Kind::FontFamily),
+    ("color", Kind::Color),
+    ("aspectRatio", Kind::Scale),
+    ("width", Kind::Scale),
+    ("height", Kind::Scale),
+    ("columns", Kind::Scale),
+    ("screens", Kind::Length),
+    ("container", Kind::Container),
+    ("z_index", Kind::Scale),
+    ("break-before", Kind::Class),
+    ("break-after", Kind::Class),
+    ("break-inside", Kind::Class),
+    ("box-decoration-break", Kind::Class),
+    ("box-sizing", Kind::Class),
+    ("display", Kind::Class),
+    ("visibility", Kind::Class),
+    ("float", Kind::Class),
+    ("clear", Kind::Class),
+    ("isolation", Kind::Class),
+    ("object-fit", Kind::Class),
+    ("overflow", Kind::Class),
+    ("overscroll-behavior", Kind::Class),
+    ("position", Kind::Class),
+    ("basis", Kind::Scale),
+    ("flex", Kind::Scale),
+    ("flex-direction", Kind::Class),
+    ("flex-wrap", Kind::Class),
+    ("flexGrow", Kind::Scale),
+    ("flexShrink", Kind::Scale),
+    ("backgroundImage", Kind::Scale),
+    ("backgroundSize", Kind::Scale),
+    ("backgroundPosition", Kind::Scale),
+    ("background-attachment", Kind::Class),
+    ("background-clip", Kind::Class),
+    ("background-origin", Kind::Class),
+    ("background-repeat", Kind::Class),
+    ("gradientColorStopPositions", Kind::Scale),
+    ("cursor", Kind::Class),
+    ("pointer-events", Kind::Class),
+    ("user-select", Kind::Class),
+    ("resize", Kind::Class),
+    ("scroll-behavior", Kind::Class),
+    ("scroll-snap-align", Kind::Class),
+    ("scroll-snap-stop", Kind::Class),
+    ("scroll-snap-type", Kind::Class),
+    ("touch-action", Kind::Class),
+    ("accent-color", Kind::Class),
+    ("appearance", Kind::Class),
+    ("will-change", Kind::Class),
+    ("fill", Kind::Class),
+    ("stroke", Kind::Class),
+    ("strokeWidth", Kind::Scale),
+    ("opacity", Kind::Scale),
+    ("boxShadow", Kind::Scale),
+    ("mix-blend-mode", Kind::Class),
+    ("background-blend-mode", Kind::Class),
+    ("border-collapse", Kind::Class),
+    ("table-layout", Kind::Class),
+    ("caption-side", Kind::Class),
+    ("list-style-position", Kind::Class),
+    ("outline-style", Kind::Class),
+    ("listStyleType", Kind::Scale),
+    ("listStyleImage", Kind::Scale),
+    ("outlineWidth", Kind::Length),
+    ("outlineOffset", Kind::Length),
+    ("transitionDuration", Kind::Scale),
+    ("accessibility", Kind::Class),
+    ("forced-color-adjust", Kind::Class),
+    ("variants", Kind::Variant),
+    ("utilities", Kind::Utility),
+    ("blur", Kind::Scale),
+    ("brightness", Kind::Scale),
+    ("contrast", Kind::Scale),
+    ("dropShadow", Kind::Filter),
+    ("grayscale", Kind::Scale),
+    ("hueRotate", Kind::Scale),
+    ("invert", Kind::Scale),
+    ("saturate", Kind::Scale),
+    ("sepia", Kind::Scale),
+    ("corePlugins", Kind::CorePlugin),
+    ("plugins", Kind::Plugin),
+    ("prefix", Kind::Text),
+    ("separator", Kind::Text),
+    ("safelist", Kind::Safelist),
+    ("blocklist", Kind::Blocklist),
+    // Read by `basis` on top of the spacing scale.
+    ("data", Kind::Scale),
+];
+
+const LENGTH_UNITS: [&str; 30] = [
+    "px", "rem", "em", "%", "vh", "vw", "vmin", "vmax", "dvh", "dvw", "svh", "svw", "lvh", "lvw",
+    "ch", "ex", "lh", "rlh", "pt", "pc", "cm", "mm", "in", "q", "cqw", "cqh", "cqi", "cqb",
+    "cqmin", "cqmax",
+];
+
+const LENGTH_KEYWORDS: [&str; 9] = [
+    "auto",
+    "none",
+    "inherit",
+    "initial",
+    "unset",
+    "revert",
+    "min-content",
+    "max-content",
+    "fit-content",
+];
+
+const LENGTH_FUNCTIONS: [&str; 6] = ["calc(", "var(", "min(", "max(", "clamp(", "env("];
+
+// Whether `value` is a CSS length such as `0.25rem`, `0` or `calc(...)`.
+// Bare numbers other than `0` are not lengths.
+fn is_length(value: &str) -> bool {
+    if LENGTH_KEYWORDS.contains(&value) {
+        return true;
+    }
+    if LENGTH_FUNCTIONS.iter().any(|name| value.starts_with(name)) {
+        return value.ends_with(')');
+    }
+    let unit_start = value
+        .find(|c: char| !(c.is_ascii_digit() || "+-.".contains(c)))
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(unit_start);
+    match number.parse::<f64>() {
+        Ok(number) if unit.is_empty() => number == 0.0,
+        Ok(_) => LENGTH_UNITS.contains(&unit.to_ascii_lowercase().as_str()),
+        Err(_) => false,
+    }
+}
+
+pub fn describe(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "a boolean",
+        Value::Number(_) => "a number",
+        Value::String(_) => "a string",
+        Value::Array(_) => "a list",
+        Value::Object(_) => "an object",
+    }
+}
+
+// Edit distance between two keys, for "did you mean" hints.
+fn distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut previous = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let current = row[j + 1];
+            row[j + 1] = (previous + usize::from(ca != *cb))
+                .min(row[j] + 1)
+                .min(current + 1);
+            previous = current;
+        }
+    }
+    row[b.len()]
+}
+
+// `message`, with the closest of `names` as a hint when there is one.
+fn unknown<'a>(message: &str, key: &str, names: impl Iterator<Item = &'a str>) -> String {
+    let closest = names.map(|name| (distance(key, name), name)).min();
+    match closest {
+        Some((distance, name)) if distance <= 2 => {
+            format!("{}, did you mean `{}`?", message, name)
+        }
+        _ => message.to_string(),
+    }
+}
+
+fn check_length(path: &str, length: &str, diagnostics: &mut Vec<Diagnostic>) {
+    if !is_length(length) {
+        diagnostics.push(Diagnostic::warning(
+            path,
+            format!("`{}` is not a valid CSS length", length),
+        ));
+    }
+}
+
+fn validate_entry(kind: Kind, path: &str, value: &Value, diagnostics: &mut Vec<Diagnostic>) {
+    let (valid, expected) = match kind {
+        Kind::Scale | Kind::Length => (is::<Scalar>(value), "a string or a number"),
+        Kind::Color => (is::<Color>(value), "a color or an object of shades"),
+        Kind::FontSize => (
+            is::<FontSizeEntry>(value),
+            "a size, `[size, lineHeight]` or `[size, { lineHeight }]`",
+        ),
+        Kind::FontFamily => (is::<FontFamily>(value), "a font or a list of fonts"),
+        Kind::Class => (
+            value.as_object().is_some_and(|style| {
+                !style.is_empty() && style.values().all(|v| v.is_string() || v.is_number())
+            }),
+            "a style object of strings and numbers",
+        ),
+        Kind::Variant => (
+            match value {
+                Value::String(variant) => variant.contains('&') || variant.starts_with('@'),
+                Value::Array(variants) => {
+                    !variants.is_empty()
+                        && variants
+                            .iter()
+                            .all(|variant| variant.as_str().is_some_and(|v| v.contains('&')))
+                }
+                _ => false,
+            },
+            "a selector with `&`, an at-rule or a list of selectors",
+        ),
+        Kind::Filter => (
+            is::<FilterValue>(value),
+            "a string, a number or a list of strings",
+        ),
+        Kind::Plugin => (
+            is::<Vec<PluginEntry>>(value),
+            "a list of `[class, [properties]]` entries",
+        ),
+        Kind::Container
+        | Kind::Text
+        | Kind::Safelist
+        | Kind::Blocklist
+        | Kind::CorePlugin
+        | Kind::Utility => (true, ""),
+    };
+    if !valid {
+        diagnostics.push(Diagnostic::error(
+            path,
+            format!("expected {}, found {}", expected, describe(value)),
+        ));
+        return;
+    }
+
+    match (kind, value) {
+        (Kind::Length, Value::String(length)) => check_length(path, length, diagnostics),
+        (Kind::Length, Value::Number(length)) => {
+            check_length(path, &length.to_string(), diagnostics)
+        }
+        (Kind::FontSize, Value::String(size)) => check_length(path, size, diagnostics),
+        (Kind::FontSize, Value::Array(entry)) => {
+            if let Some(size) = entry.first().and_then(Value::as_str) {
+                check_length(path, size, diagnostics);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn validate_container(value: &Value, diagnostics: &mut Vec<Diagnostic>) {
+    let container = match value.as_object() {
+        Some(container) => container,
+        None => {
+            diagnostics.push(Diagnostic::error(
+                "container",
+                format!(
+                    "expected an object with `center` and `padding`, found {}",
+                    describe(value)
+                ),
+            ));
+            return;
+        }
+    };
+    for (key, value) in container {
+        let path = format!("container.{}", key);
+        match (key.as_str(), value) {
+            ("center", Value::Bool(_)) => {}
+            ("center", _) => diagnostics.push(Diagnostic::error(
+                &path,
+                format!("expected `true` or `false`, found {}", describe(value)),
+            )),
+            ("padding", Value::String(padding)) => check_length(&path, padding, diagnostics),
+            ("padding", Value::Object(paddings)) => {
+                for (screen, padding) in paddings {
+                    let path = format!("{}.{}", path, screen);
+                    match padding.as_str() {
+                        Some(padding) => check_length(&path, padding, diagnostics),
+                        None => diagnostics.push(Diagnostic::error(
+                            &path,
+                            format!("expected a length, found {}", describe(padding)),
+                        )),
+                    }
+                }
+            }
+            ("padding", _) => diagnostics.push(Diagnostic::error(
+                &path,
+                format!(
+                    "expected a length or an object of lengths by screen, found {}",
+                    describe(value)
+                ),
+            )),
+            _ => diagnostics.push(Diagnostic::warning(
+                &path,
+                "unknown container option, expected `center` or `padding`".to_string(),
+            )),
+        }
+    }
+}
+
+// `{ pattern, variants }` of the safelist.
+fn validate_pattern(
+    entry: &Map<String, Value>,
+    variants: &[&str],
+    path: &str,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> Result<(), String> {
+    if !entry.contains_key("pattern") {
+        return Err("missing `pattern`".to_string());
+    }
+    for (key, value) in entry {
+        match (key.as_str(), value) {
+            ("pattern", Value::String(pattern)) => {
+                compile(pattern)?;
+            }
+            ("pattern", _) => return Err("`pattern` must be a string".to_string()),
+            ("variants", Value::Array(names)) => {
+                for name in names {
+                    let name = name
+                        .as_str()
+                        .ok_or("`variants` must be a list of variant names")?;
+                    if !variants.contains(&name) {
+                        diagnostics.push(Diagnostic::warning(
+                            path,
+                            format!("unknown variant `{}`", name),
+                        ));
+                    }
+                }
+            }
+            ("variants", _) => {
+                return Err("`variants` must be a list of variant names".to_string());
+            }
+            _ => {
+                return Err(format!(
+                    "unknown option `{}`, expected `pattern` or `variants`",
+                    key
+                ))
+            }
+        }
+    }
+    Ok(())
+}
+
+fn validate_classes(
+    kind: Kind,
+    obj: &Map<String, Value>,
+    key: &str,
+    value: &Value,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    let items = match value.as_array() {
+        Some(items) => items,
+        None => {
+            diagnostics.push(Diagnostic::error(
+                key,
+                format!("expected a list, found {}", describe(value)),
+            ));
+            return;
+        }
+    };
+    let variants: Vec<&str> = ["variants", "screens"]
+        .iter()
+        .filter_map(|section| obj.get(*section)?.as_object())
+        .flat_map(|section| section.keys().map(String::as_str))
+        .collect();
+    for (index, item) in items.iter().enumerate() {
+        let path = format!("{}[{}]", key, index);
+        let result = match item {
+            Value::String(class) => ClassPattern::try_from(class.clone()).map(|_| ()),
+            Value::Object(entry) if kind == Kind::Safelist => {
+                validate_pattern(entry, &variants, &path, diagnostics)
+            }
+            _ if kind == Kind::Safelist => Err(format!(
+                "expected a class, a `/pattern/` or `{{ pattern, variants }}`, found {}",
+                describe(item)
+            )),
+            _ => Err(format!(
+                "expected a class or a `/pattern/`, found {}",
+                describe(item)
+            )),
+        };
+        if let Err(message) = result {
+            diagnostics.push(Diagnostic::error(&path, message));
+        }
+    }
+}
+
+fn validate_core_plugins(value: &Value, diagnostics: &mut Vec<Diagnostic>) {
+    let mut names = Vec::new();
+    match value {
+        Value::Array(items) => {
+            for (index, item) in items.iter().enumerate() {
+                let path = format!("corePlugins[{}]", index);
+                match item.as_str() {
+                    Some(name) => names.push((path, name)),
+                    None => diagnostics.push(Diagnostic::error(
+                        &path,
+                        format!("expected a plugin name, found {}", describe(item)),
+                    )),
+                }
+            }
+        }
+        Value::Object(toggles) => {
+            for (name, enabled) in toggles {
+                let path = format!("corePlugins.{}", name);
+                if !enabled.is_boolean() {
+                    diagnostics.push(Diagnostic::error(
+                        &path,
+                        format!("expected `true` or `false`, found {}", describe(enabled)),
+                    ));
+                }
+                names.push((path, name));
+            }
+        }
+        _ => diagnostics.push(Diagnostic::error(
+            "corePlugins",
+            format!(
+                "expected a list of plugins to keep or an object of toggles, found {}",
+                describe(value)
+            ),
+        )),
+    }
+
+    for (path, name) in names {
+        if !is_core_plugin(name) {
+            let plugins = CORE_PLUGINS.iter().copied().chain(["preflight"]);
+            diagnostics.push(Diagnostic::warning(
+                &path,
+                unknown(&format!("unknown core plugin `{}`", name), name, plugins),
+            ));
+        }
+    }
+}
+
+fn validate_text(key: &str, value: &Value, diagnostics: &mut Vec<Diagnostic>) {
+    match value {
+        Value::String(text) if text.is_empty() && key == "separator" => {
+            diagnostics.push(Diagnostic::error(key, "can't be empty".to_string()))
+        }
+        Value::String(_) => {}
+        _ => diagnostics.push(Diagnostic::error(
+            key,
+            format!("expected a string, found {}", describe(value)),
+        )),
+    }
+}
+
+// An entry of `utilities`: a style object, or the options of a family of
+// classes when it has a `property`.
+fn validate_utility(
+    obj: &Map<String, Value>,
+    path: &str,
+    value: &Value,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    let entry = match value.as_object() {
+        Some(entry) => entry,
+        None => {
+            diagnostics.push(Diagnostic::error(
+                path,
+                format!(
+                    "expected a style object or `{{ property, values }}`, found {}",
+                    describe(value)
+                ),
+            ));
+            return;
+        }
+    };
+    if !entry.contains_key("property") {
+        return;
+    }
+    if !entry.contains_key("values") {
+        diagnostics.push(Diagnostic::error(path, "missing `values`".to_string()));
+    }
+    for (option, value) in entry {
+        let path = format!("{}.{}", path, option);
+        let message = match (option.as_str(), value) {
+            ("property", value) if is::<Properties>(value) => continue,
+            ("property", _) => format!(
+                "expected a property or a list of properties, found {}",
+                describe(value)
+            ),
+            ("values", Value::String(section)) => match theme_section(obj, section) {
+                Some(_) => continue,
+                None => format!("`{}` is not in the theme", section),
+            },
+            ("values", value) if is::<IndexMap<String, Scalar>>(value) => continue,
+            ("values", _) => format!(
+                "expected a theme path or an object of values, found {}",
+                describe(value)
+            ),
+            ("supportsNegative", Value::Bool(_)) => continue,
+            ("supportsNegative", _) => {
+                format!("expected `true` or `false`, found {}", describe(value))
+            }
+            _ => unknown(
+                "unknown utility option",
+                option,
+                ["property", "values", "supportsNegative"].into_iter(),
+            ),
+        };
+        diagnostics.push(Diagnostic::error(&path, message));
+    }
+}
+
+// Custom theme sections that `utilities` take their values from, e.g.
+// `tabSize` for `theme.tabSize`. They aren't unknown keys.
+fn utility_sections(obj: &Map<String, Value>) -> Vec<&str> {
+    obj.get("utilities")
+        .and_then(Value::as_object)
+        .into_iter()
+        .flatten()
+        .filter_map(|(_, entry)| entry.get("values")?.as_str())
+        .filter_map(|path| {
+            path.strip_prefix("theme.")
+                .unwrap_or(path)
+                .split('.')
+                .next()
+        })
+        .map(section_name)
+        .collect()
+}
+
+fn validate_entries(kind: Kind, key: &str, value: &Value, diagnostics: &mut Vec<Diagnostic>) {
+    let entries = match value.as_object() {
+        Some(entries) => entries,
+        None => {
+            diagnostics.push(Diagnostic::error(
+                key,
+                format!("expected an object, found {}", describe(value)),
+            ));
+            return;
+        }
+    };
+    for (entry, value) in entries {
+        validate_entry(kind, &format!("{}.{}", key, entry), value, diagnostics);
+    }
+}
+
+/// Check every section of a config against the shape its utilities expect.
+/// Entries that can't be used are errors; unknown keys and invalid lengths
+/// are warnings.
+pub fn validate(obj: &Map<String, Value>) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    let custom_sections = utility_sections(obj);
+    for (key, value) in obj {
+        let kind = match SECTIONS.iter().find(|(name, _)| name == key) {
+            Some((_, kind)) => *kind,
+            None if custom_sections.contains(&key.as_str()) => continue,
+            None => {
+                let sections = SECTIONS.iter().map(|(name, _)| theme_key(name));
+                diagnostics.push(Diagnostic::warning(
+                    key,
+                    unknown("unknown config key", key, sections),
+                ));
+                continue;
+            }
+        };
+        match kind {
+            Kind::Container => validate_container(value, &mut diagnostics),
+            Kind::Text => validate_text(key, value, &mut diagnostics),
+            Kind::Safelist | Kind::Blocklist => {
+                validate_classes(kind, obj, key, value, &mut diagnostics)
+            }
+            Kind::CorePlugin => validate_core_plugins(value, &mut diagnostics),
+            Kind::Utility => match value.as_object() {
+                Some(utilities) => {
+                    for (name, utility) in utilities {
+                        let path = format!("utilities.{}", name);
+                        validate_utility(obj, &path, utility, &mut diagnostics);
+                    }
+                }
+                None => diagnostics.push(Diagnostic::error(
+                    key,
+                    format!("expected an object, found {}", describe(value)),
+                )),
+            },
+            _ => validate_entries(kind, key, value, &mut diagnostics),
+        }
+    }
+    diagnostics
+}
@@ -1,5 +1,7 @@
+use std::cell::RefCell;
 use std::fs::File;
 use std::io::{BufWriter, Write};
+use std::rc::Rc;
 
 pub trait Buffer {
     fn write(&mut self, data: &str);
@@ -23,3 +25,22 @@ impl Buffer for BufferWriter {
         }
     }
 }
+
+// Collects the output in memory, for CSS that gets spliced into a stylesheet.
+pub struct StringBuffer {
+    output: Rc<RefCell<String>>,
+}
+
+impl StringBuffer {
+    pub fn new(output: Rc<RefCell<String>>) -> Self {
+        Self { output }
+    }
+}
+
+impl Buffer for StringBuffer {
+    fn write(&mut self, data: &str) {
+        let mut output = self.output.borrow_mut();
+        output.push_str(data);
+        output.push('\n');
+    }
+}
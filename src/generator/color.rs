@@ -0,0 +1,39 @@
+/// Channels of a `#rgb` or `#rrggbb` color.
+pub fn hex_to_rgb(color: &str) -> Option<(u8, u8, u8)> {
+    let hex = color.strip_prefix('#')?;
+    let channel = |range: &str| u8::from_str_radix(range, 16).ok();
+    match hex.len() {
+        3 => {
+            let mut channels = hex.chars().map(|c| channel(&format!("{}{}", c, c)));
+            Some((channels.next()??, channels.next()??, channels.next()??))
+        }
+        6 => Some((
+            channel(&hex[0..2])?,
+            channel(&hex[2..4])?,
+            channel(&hex[4..6])?,
+        )),
+        _ => None,
+    }
+}
+
+/// `color` with the given alpha, e.g. `rgb(59 130 246 / 0.5)`. Only hex
+/// colors and `oklch()` colors without alpha, such as generated palettes,
+/// can carry an opacity modifier.
+pub fn with_alpha(color: &str, alpha: &str) -> Option<String> {
+    if let Some(channels) = color.strip_prefix("oklch(") {
+        let channels = channels.strip_suffix(')').filter(|c| !c.contains('/'))?;
+        return Some(format!("oklch({} / {})", channels, alpha));
+    }
+    let (r, g, b) = hex_to_rgb(color)?;
+    Some(format!("rgb({} {} {} / {})", r, g, b, alpha))
+}
+
+/// `color` mixed with transparency for colors that aren't known at build
+/// time, e.g. `color-mix(in srgb, var(--color-blue-500) 50%, transparent)`.
+pub fn mix_alpha(color: &str, alpha: &str) -> String {
+    let percentage = match alpha.parse::<f64>() {
+        Ok(alpha) => format!("{}%", (alpha * 1000.0).round() / 10.0),
+        Err(_) => alpha.to_string(),
+    };
+    format!("color-mix(in srgb, {} {}, transparent)", color, percentage)
+}
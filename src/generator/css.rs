@@ -1,44 +1,332 @@
-use super::utils::EscapeClassName;
+use super::color::{mix_alpha, with_alpha};
+use super::order::{plugin_order, Layer, SortKey};
+use super::rule::StyleRule;
+use super::utils::{
+    arbitrary_value, is_length, split_variants, strip_class_prefix, EscapeClassName,
+};
+use super::variables::Variables;
 use super::Buffer;
-use crate::config::Config;
+use crate::config::{Config, FilterValue, Mode, BACKDROP_FILTERS, FILTERS};
+use serde_json::{Map, Value};
+use std::cmp::Reverse;
+
+// Selectors and wrapping at-rules of the class currently being generated,
+// e.g. `md:hover:underline` -> `.md\:hover\:underline:hover` inside
+// `@media (min-width: 768px)`. Variants with several selectors, such as
+// `hocus`, give one selector each.
+struct Variant {
+    base: String,
+    selectors: Vec<String>,
+    at_rules: Vec<String>,
+}
+
+// Everything generated for one class, written out in `SortKey` order.
+struct Rule {
+    key: SortKey,
+    css: Vec<StyleRule>,
+    // Cleared when the plugin is turned off through `corePlugins`.
+    enabled: bool,
+}
+
+// Expand a CSS-in-JS style object into rules: strings are declarations,
+// `@` keys wrap their contents in that at-rule and other keys are nested
+// selectors, with `&` standing for the parent.
+fn object_rules(selector: &str, at_rules: &[String], obj: &Map<String, Value>) -> Vec<StyleRule> {
+    let mut css = StyleRule::new(selector, Vec::new());
+    css.at_rules = at_rules.to_vec();
+    let mut nested = Vec::new();
+    for (key, value) in obj {
+        match value {
+            Value::String(value) => css.declarations.push((key.clone(), value.clone())),
+            Value::Number(value) => css.declarations.push((key.clone(), value.to_string())),
+            Value::Object(value) if key.starts_with('@') => {
+                let mut at_rules = at_rules.to_vec();
+                at_rules.push(key.clone());
+                nested.extend(object_rules(selector, &at_rules, value));
+            }
+            Value::Object(value) => {
+                let selector = match key.contains('&') {
+                    true => key.replace('&', selector),
+                    false => format!("{} {}", selector, key),
+                };
+                nested.extend(object_rules(&selector, at_rules, value));
+            }
+            _ => {}
+        }
+    }
+
+    let mut rules = Vec::new();
+    if !css.declarations.is_empty() {
+        rules.push(css);
+    }
+    rules.extend(nested);
+    rules
+}
+
+// Namespaces of the theme variables, in the order they are written.
+const COLOR: usize = 0;
+const SPACING: usize = 1;
+const TEXT: usize = 2;
+
+// Position and style object of `key` in a section of classes.
+fn find_class<'a>(
+    section: &'a Map<String, Value>,
+    key: &str,
+) -> Option<(usize, &'a Map<String, Value>)> {
+    let obj = section.get(key)?.as_object()?;
+    Some((section.keys().position(|item| item == key)?, obj))
+}
 
 pub struct Css {
     config: Config,
     writer: Box<dyn Buffer>,
+    variant: Option<Variant>,
+    rules: Vec<Rule>,
+    variables: Variables,
 }
 
 impl Css {
     pub fn new(writer: Box<dyn Buffer>, config: Config) -> Self {
-        Self { config, writer }
+        Self {
+            config,
+            writer,
+            variant: None,
+            rules: Vec::new(),
+            variables: Variables::default(),
+        }
+    }
+
+    /// The theme variables referenced by the rules generated so far.
+    pub fn take_variables(&mut self) -> Variables {
+        std::mem::take(&mut self.variables)
+    }
+
+    /// Write the `:root` block declaring the referenced theme variables.
+    pub fn write_variables(&mut self) {
+        let variables = self.take_variables();
+        if !variables.is_empty() {
+            self.writer.write(&variables.to_string());
+        }
+    }
+
+    // Position of `key` in a section of the loaded config.
+    fn theme_index(&self, section: &str, key: &str) -> usize {
+        self.config
+            .theme
+            .get(section)
+            .and_then(Value::as_object)
+            .and_then(|section| section.keys().position(|item| item == key))
+            .unwrap_or(usize::MAX)
+    }
+
+    // `value` of the theme, or a reference to it when theme variables are
+    // enabled.
+    fn theme_value(&mut self, order: Vec<usize>, name: &str, value: &str) -> String {
+        if !self.config.css_variables {
+            return value.to_string();
+        }
+        self.variables.insert(order, name, value);
+        format!("var({})", name)
+    }
+
+    /// Start collecting the rules of `class` and return its utility part,
+    /// without variants. Returns `None` when one of the variants is unknown.
+    pub fn start_class(&mut self, class: &str) -> Option<String> {
+        self.variant = None;
+        let (variants, utility) = split_variants(class, &self.config.separator);
+        let utility = utility.to_string();
+
+        let mut variant_order = Vec::new();
+        let mut selectors = vec![format!(".{}", class.escape_class_name())];
+        let mut at_rules = Vec::new();
+        for name in variants.iter().rev() {
+            let variant = self.config.get_variant(name)?;
+            match variant.first() {
+                Some(at_rule) if at_rule.starts_with('@') => at_rules.insert(0, at_rule.clone()),
+                _ => {
+                    selectors = variant
+                        .iter()
+                        .flat_map(|template| {
+                            selectors
+                                .iter()
+                                .map(move |selector| template.replace('&', selector))
+                        })
+                        .collect()
+                }
+            }
+            variant_order.extend(self.config.get_variant_order(name));
+        }
+        variant_order.sort_unstable_by(|a, b| b.cmp(a));
+
+        self.rules.push(Rule {
+            key: SortKey {
+                layer: Layer::Utilities,
+                variants: variant_order,
+                plugin: plugin_order(""),
+                entry: 0,
+                properties: Reverse(0),
+                utility: utility.clone(),
+                class: class.to_string(),
+            },
+            css: Vec::new(),
+            enabled: true,
+        });
+        // Generators write selectors for the bare utility, which are then
+        // swapped for the full class.
+        if utility != class {
+            self.variant = Some(Variant {
+                base: format!(".{}", utility.escape_class_name()),
+                selectors,
+                at_rules,
+            });
+        }
+        Some(utility)
+    }
+
+    /// Remove the configured prefix from the utility part of the current
+    /// class, for the core and config utilities. Classes of the stylesheet
+    /// are looked up before, as written. Returns `None` when the class
+    /// lacks the prefix.
+    pub fn strip_prefix(&mut self, utility: &str) -> Option<String> {
+        let stripped = strip_class_prefix(utility, &self.config.prefix)?;
+        if stripped == utility {
+            return Some(stripped);
+        }
+        let base = format!(".{}", stripped.escape_class_name());
+        match &mut self.variant {
+            Some(variant) => variant.base = base,
+            None => {
+                self.variant = Some(Variant {
+                    base,
+                    selectors: vec![format!(".{}", utility.escape_class_name())],
+                    at_rules: Vec::new(),
+                })
+            }
+        }
+        if let Some(rule) = self.rules.last_mut() {
+            rule.key.utility = stripped.clone();
+        }
+        Some(stripped)
     }
 
-    fn append_css(&mut self, css: &str) {
-        self.writer.write(css)
+    /// Write every collected rule in cascade order.
+    pub fn flush(&mut self) {
+        self.write_rules(|_| true);
+    }
+
+    /// Like `flush`, but only writes the rules of `layer`.
+    pub fn flush_layer(&mut self, layer: Layer) {
+        self.write_rules(|key| key.layer == layer);
+    }
+
+    fn write_rules(&mut self, filter: impl Fn(&SortKey) -> bool) {
+        for rule in self.take_rules(filter) {
+            self.writer.write(&rule.to_string());
+        }
+    }
+
+    /// Take the collected rules in cascade order instead of writing them.
+    pub fn take_rules(&mut self, filter: impl Fn(&SortKey) -> bool) -> Vec<StyleRule> {
+        let mut rules = std::mem::take(&mut self.rules);
+        rules.retain(|rule| rule.enabled && !rule.css.is_empty() && filter(&rule.key));
+        rules.sort_by(|a, b| a.key.cmp(&b.key));
+        rules.into_iter().flat_map(|rule| rule.css).collect()
+    }
+
+    fn set_plugin(&mut self, name: &str, entry: usize) {
+        if let Some(rule) = self.rules.last_mut() {
+            rule.key.plugin = plugin_order(name);
+            rule.key.entry = entry;
+            rule.enabled = self.config.core_plugins.enabled(name);
+        }
+    }
+
+    fn set_layer(&mut self, layer: Layer) {
+        if let Some(rule) = self.rules.last_mut() {
+            rule.key.layer = layer;
+        }
+    }
+
+    fn append_rule(&mut self, mut css: StyleRule) {
+        if let Some(variant) = &self.variant {
+            // Only swap the class itself: `.card:hover` becomes
+            // `.md\:card:hover`, but `.m-2\.5` is left alone for `m-2`.
+            if let Some(rest) = css.selector.strip_prefix(&variant.base) {
+                if !rest.starts_with(|c: char| c.is_alphanumeric() || "-_\\".contains(c)) {
+                    let selectors: Vec<String> = variant
+                        .selectors
+                        .iter()
+                        .map(|selector| format!("{}{}", selector, rest))
+                        .collect();
+                    css.selector = selectors.join(", ");
+                }
+            }
+            css.at_rules.splice(0..0, variant.at_rules.iter().cloned());
+        }
+        if let Some(rule) = self.rules.last_mut() {
+            rule.key.properties.0 += css.declarations.len();
+            rule.css.push(css);
+        }
+    }
+
+    fn append_declarations(&mut self, line: &str, declarations: Vec<(String, String)>) {
+        let selector = format!(".{}", line.escape_class_name());
+        self.append_rule(StyleRule::new(&selector, declarations));
+    }
+
+    fn append_declaration(&mut self, line: &str, property: &str, value: &str) {
+        self.append_declarations(line, vec![(property.to_string(), value.to_string())]);
     }
 
     pub fn generate_font_size(&mut self, line: &str) {
         if line.starts_with("text-") {
-            let size = line.split('-').last().unwrap();
+            let size = line.split('-').next_back().unwrap();
             if let Some(font_size) = self.config.get_font_size(size) {
-                let css = &format!(
-                    ".text-{} {{\n\tfont-size: {};\n\tline-height: {};\n}}",
-                    size, font_size.value, font_size.line_height
-                );
-                self.append_css(css);
+                let font_size = font_size.clone();
+                let index = self.theme_index("font_size", size);
+                let name = format!("--text-{}", size);
+                let value = self.theme_value(vec![TEXT, index, 0], &name, &font_size.value);
+                let mut declarations = vec![("font-size".to_string(), value)];
+                if let Some(line_height) = &font_size.line_height {
+                    let line_height = self.theme_value(
+                        vec![TEXT, index, 1],
+                        &format!("{}--line-height", name),
+                        line_height,
+                    );
+                    declarations.push(("line-height".into(), line_height));
+                }
+                let css = StyleRule::new(&format!(".text-{}", size), declarations);
+                self.set_plugin("font_size", 0);
+                self.append_rule(css);
             }
         }
     }
 
     pub fn generate_font_weight(&mut self, line: &str) {
-        let size = line.split('-').last().unwrap();
+        let size = line.split('-').next_back().unwrap();
         if let Some(font_size) = self.config.get_font_weight(size) {
-            let css = &format!(".font-{} {{\n\tfont-size: {};\n}}", size, font_size);
-            self.append_css(css);
+            let css = StyleRule::new(
+                &format!(".font-{}", size),
+                vec![("font-weight".into(), font_size.to_string())],
+            );
+            self.set_plugin("font_weight", 0);
+            self.append_rule(css);
         }
     }
 
+    pub fn generate_font_family(&mut self, line: &str) -> Option<()> {
+        let fonts = self
+            .config
+            .font_family
+            .get(line.strip_prefix("font-")?)?
+            .clone();
+        self.set_plugin("font_family", 0);
+        self.append_declaration(line, "font-family", &fonts);
+        Some(())
+    }
+
     pub fn generate_line_height(&mut self, prefix: &str, line: &str) {
-        let mut space = line.split('-').last().unwrap().to_string();
+        let mut space = line.split('-').next_back().unwrap().to_string();
         let mut space_size = String::new();
         if let Some(size) = self.config.get_line_height(&space) {
             space_size.push_str(size);
@@ -46,56 +334,478 @@ impl Css {
             return;
         }
 
-        space = space.replace(".", "\\.");
+        space = space.replace('.', "\\.");
         if prefix == "leading" {
-            let css = &format!(".leading-{} {{\n\tline-height: {};\n}}", space, space_size);
-            self.append_css(css);
+            let css = StyleRule::new(
+                &format!(".leading-{}", space),
+                vec![("line-height".into(), space_size)],
+            );
+            self.set_plugin("line_height", 0);
+            self.append_rule(css);
         }
     }
 
-    pub fn generate_background_color(&mut self, line: &str) {
-        let classes: Vec<&str> = line.split('-').collect();
-        if classes.len() == 2 {
-            let name = classes.last().unwrap();
-            if let Some(val) = self.config.get_color_str(name) {
-                let css = &format!(".bg-{} {{\n\tbackground-color: {};\n}}", name, val);
-                self.append_css(css);
+    /// Resolve a color key with an optional opacity modifier, e.g.
+    /// `blue-500`, `blue-500/50` or `[#1da1f2]`.
+    fn resolve_color(&mut self, key: &str) -> Option<String> {
+        let (name, opacity) = match key.split_once('/') {
+            Some((name, opacity)) => (name, Some(opacity)),
+            None => (key, None),
+        };
+        let color = match arbitrary_value(name) {
+            Some(color) if is_length(&color) => return None,
+            Some(color) => color,
+            None => self.theme_color(name)?,
+        };
+        match opacity {
+            Some(opacity) if color.starts_with("var(") => {
+                Some(mix_alpha(&color, &self.resolve_opacity(opacity)?))
             }
+            Some(opacity) => with_alpha(&color, &self.resolve_opacity(opacity)?),
+            None => Some(color),
         }
+    }
 
-        if classes.len() == 3 {
-            let name = classes[1];
-            let variant = classes[2];
-            if let Some(color) = self.config.get_color_map(name) {
-                if let Some(val) = color.get(variant) {
-                    let css = &format!(
-                        ".bg-{}-{} {{\n\tbackground-color: {};\n}}",
-                        name,
-                        variant,
-                        val.as_str().unwrap()
-                    );
-                    self.append_css(css);
-                }
+    fn theme_color(&mut self, name: &str) -> Option<String> {
+        let color = self.config.get_color(name)?.to_string();
+        let order = match self.config.get_color_str(name) {
+            Some(_) => vec![COLOR, self.theme_index("color", name)],
+            None => {
+                let (palette, shade) = name.rsplit_once('-')?;
+                let shade = self
+                    .config
+                    .get_color_map(palette)?
+                    .keys()
+                    .position(|key| key == shade);
+                vec![COLOR, self.theme_index("color", palette), shade?]
+            }
+        };
+        Some(self.theme_value(order, &format!("--color-{}", name), &color))
+    }
+
+    // A value of the spacing scale as a theme variable, e.g. the `4` of
+    // `-mt-4` as `calc(var(--spacing-4) * -1)`.
+    fn spacing_variable(&mut self, key: &str, value: &str, is_negative: bool) -> Option<String> {
+        if !self.config.css_variables {
+            return None;
+        }
+        let spacing = self.config.spacing.get(key)?.clone();
+        let positive = match is_negative {
+            true => value.strip_prefix('-')?,
+            false => value,
+        };
+        if positive != spacing {
+            return None;
+        }
+        let order = vec![SPACING, self.theme_index("spacing", key)];
+        let name = format!("--spacing-{}", key.replace(['.', '/'], "_"));
+        let value = self.theme_value(order, &name, &spacing);
+        match is_negative {
+            true => Some(format!("calc({} * -1)", value)),
+            false => Some(value),
+        }
+    }
+
+    /// Resolve an opacity key such as `50` or `[.67]`.
+    fn resolve_opacity(&self, key: &str) -> Option<String> {
+        match arbitrary_value(key) {
+            Some(value) => Some(value),
+            None => self.config.opacity.get(key).cloned(),
+        }
+    }
+
+    pub fn generate_opacity(&mut self, line: &str) -> Option<()> {
+        let opacity = self.resolve_opacity(line.strip_prefix("opacity-")?)?;
+        self.set_plugin("opacity", 0);
+        self.append_declaration(line, "opacity", &opacity);
+        Some(())
+    }
+
+    pub fn generate_box_shadow(&mut self, line: &str) -> Option<()> {
+        let key = match line {
+            "shadow" => "DEFAULT",
+            _ => line.strip_prefix("shadow-")?,
+        };
+        let shadow = self.config.box_shadow.get(key)?.clone();
+        self.set_plugin("box-shadow", 0);
+        self.append_declaration(line, "box-shadow", &shadow);
+        Some(())
+    }
+
+    pub fn generate_transition_duration(&mut self, line: &str) -> Option<()> {
+        let key = line.strip_prefix("duration-")?;
+        let duration = match arbitrary_value(key) {
+            Some(duration) => duration,
+            None => self.config.transition_duration.get(key)?.clone(),
+        };
+        self.set_plugin("transition-duration", 0);
+        self.append_declaration(line, "transition-duration", &duration);
+        Some(())
+    }
+
+    pub fn generate_background(&mut self, line: &str) -> Option<()> {
+        let key = line.strip_prefix("bg-")?;
+        let (property, value) = if let Some(value) = arbitrary_value(key) {
+            if value.starts_with("url(") || value.contains("gradient(") {
+                ("background-image", value)
+            } else {
+                ("background-color", value)
             }
+        } else if let Some(image) = self.config.background_image.get(key) {
+            ("background-image", image.to_string())
+        } else if let Some(size) = self.config.background_size.get(key) {
+            ("background-size", size.to_string())
+        } else if let Some(position) = self.config.background_position.get(key) {
+            ("background-position", position.to_string())
+        } else {
+            ("background-color", self.resolve_color(key)?)
+        };
+
+        self.set_plugin(property, 0);
+        self.append_declaration(line, property, &value);
+        Some(())
+    }
+
+    pub fn generate_color(&mut self, prefix: &str, property: &str, line: &str) -> Option<()> {
+        let key = line.strip_prefix(prefix)?.strip_prefix('-')?;
+        let color = self.resolve_color(key)?;
+        self.set_plugin(property, 0);
+        self.append_declaration(line, property, &color);
+        Some(())
+    }
+
+    pub fn generate_stroke_width(&mut self, line: &str) -> Option<()> {
+        let key = line.strip_prefix("stroke-")?;
+        let width = match arbitrary_value(key) {
+            Some(width) if is_length(&width) => width,
+            Some(_) => return None,
+            None => self.config.stroke_width.get(key)?.to_string(),
+        };
+        self.set_plugin("stroke-width", 0);
+        self.append_declaration(line, "stroke-width", &width);
+        Some(())
+    }
+
+    pub fn generate_border_spacing(&mut self, line: &str) -> Option<()> {
+        let key = line.strip_prefix("border-spacing-")?;
+        let (axes, key) = if let Some(key) = key.strip_prefix("x-") {
+            (&["x"][..], key)
+        } else if let Some(key) = key.strip_prefix("y-") {
+            (&["y"][..], key)
+        } else {
+            (&["x", "y"][..], key)
+        };
+        let value = match arbitrary_value(key) {
+            Some(value) => value,
+            None => self.config.spacing.get(key)?.clone(),
+        };
+
+        self.set_plugin("border-spacing", 0);
+        let mut declarations: Vec<(String, String)> = axes
+            .iter()
+            .map(|axis| (format!("--tw-border-spacing-{}", axis), value.clone()))
+            .collect();
+        declarations.push((
+            "border-spacing".into(),
+            "var(--tw-border-spacing-x, 0) var(--tw-border-spacing-y, 0)".into(),
+        ));
+        self.append_declarations(line, declarations);
+        Some(())
+    }
+
+    pub fn generate_list_style(&mut self, line: &str) -> Option<()> {
+        let key = line.strip_prefix("list-")?;
+        if let Some(key) = key.strip_prefix("image-") {
+            let image = match arbitrary_value(key) {
+                Some(image) => image,
+                None => self.config.list_style_image.get(key)?.to_string(),
+            };
+            self.set_plugin("list-style-image", 0);
+            self.append_declaration(line, "list-style-image", &image);
+        } else {
+            let style = self.config.list_style_type.get(key)?.to_string();
+            self.set_plugin("list-style-type", 0);
+            self.append_declaration(line, "list-style-type", &style);
         }
+        Some(())
+    }
+
+    pub fn generate_outline(&mut self, line: &str) -> Option<()> {
+        let key = line.strip_prefix("outline-")?;
+        if let Some(key) = key.strip_prefix("offset-") {
+            let offset = match arbitrary_value(key) {
+                Some(offset) if is_length(&offset) => offset,
+                Some(_) => return None,
+                None => self.config.outline_offset.get(key)?.to_string(),
+            };
+            self.set_plugin("outline-offset", 0);
+            self.append_declaration(line, "outline-offset", &offset);
+            return Some(());
+        }
+
+        let (property, value) = match arbitrary_value(key) {
+            Some(width) if is_length(&width) => ("outline-width", width),
+            _ => match self.config.outline_width.get(key) {
+                Some(width) => ("outline-width", width.to_string()),
+                None => ("outline-color", self.resolve_color(key)?),
+            },
+        };
+        self.set_plugin(property, 0);
+        self.append_declaration(line, property, &value);
+        Some(())
+    }
+
+    pub fn generate_gradient_stop(&mut self, prefix: &str, line: &str) -> Option<()> {
+        let key = line.strip_prefix(prefix)?.strip_prefix('-')?;
+        let position = match arbitrary_value(key) {
+            Some(value) if value.ends_with('%') => Some(value),
+            Some(_) => None,
+            None => self.config.gradient_color_stop_positions.get(key).cloned(),
+        };
+
+        let declarations = if let Some(position) = position {
+            vec![(format!("--tw-gradient-{}-position", prefix), position)]
+        } else {
+            let color = self.resolve_color(key)?;
+            let transparent =
+                with_alpha(&color, "0").unwrap_or_else(|| "rgb(255 255 255 / 0)".to_string());
+            match prefix {
+                "from" => vec![
+                    (
+                        "--tw-gradient-from".into(),
+                        format!("{} var(--tw-gradient-from-position,)", color),
+                    ),
+                    (
+                        "--tw-gradient-to".into(),
+                        format!("{} var(--tw-gradient-to-position,)", transparent),
+                    ),
+                    (
+                        "--tw-gradient-stops".into(),
+                        "var(--tw-gradient-from), var(--tw-gradient-to)".into(),
+                    ),
+                ],
+                "via" => vec![
+                    (
+                        "--tw-gradient-to".into(),
+                        format!("{} var(--tw-gradient-to-position,)", transparent),
+                    ),
+                    (
+                        "--tw-gradient-stops".into(),
+                        format!(
+                            "var(--tw-gradient-from), {} var(--tw-gradient-via-position,), var(--tw-gradient-to)",
+                            color
+                        ),
+                    ),
+                ],
+                _ => vec![(
+                    "--tw-gradient-to".into(),
+                    format!("{} var(--tw-gradient-to-position,)", color),
+                )],
+            }
+        };
+
+        // Stops keep from -> via -> to order so `via` and `to` can override
+        // the transparent end that `from` sets up.
+        let entry = ["from", "via", "to"]
+            .iter()
+            .position(|stop| *stop == prefix);
+        self.set_plugin("gradient-color-stops", entry.unwrap_or_default());
+        self.append_declarations(line, declarations);
+        Some(())
     }
 
     pub fn generate_aspect_ratio(&mut self, line: &str) {
-        let key = line.split('-').last().unwrap();
+        let key = line.split('-').next_back().unwrap();
         let value = self.config.get_aspect_ratio(key);
         if let Some(val) = value {
-            let css = &format!(".aspect-{} {{\n\taspect-ratio: {};\n}}", key, val);
-            self.append_css(css);
+            let css = StyleRule::new(
+                &format!(".aspect-{}", key),
+                vec![("aspect-ratio".into(), val.to_string())],
+            );
+            self.set_plugin("aspect-ratio", 0);
+            self.append_rule(css);
         }
     }
 
-    pub fn generate_break_point(&mut self, line: &str) {
-        let value = self.config.get_break_point(&format!(".{}", line));
-        if let Some(val) = value {
-            let (key, val) = val.as_object().unwrap().iter().next().unwrap();
-            let css = &format!(".{} {{\n\t{}: {};\n}}", line, key, val.as_str().unwrap());
-            self.append_css(css);
+    pub fn generate_break_point(&mut self, line: &str) -> Option<()> {
+        let (plugin, value) = self.config.get_break_point(&format!(".{}", line))?;
+        let plugin = plugin.to_string();
+        let (key, val) = value.as_object()?.iter().next()?;
+        let val = match val {
+            Value::String(val) => val.clone(),
+            Value::Number(val) => val.to_string(),
+            _ => return None,
+        };
+        let css = StyleRule::new(&format!(".{}", line), vec![(key.to_string(), val)]);
+        self.set_plugin(&plugin, 0);
+        self.append_rule(css);
+        Some(())
+    }
+
+    pub fn generate_filter(&mut self, line: &str) -> Option<()> {
+        let (is_negative, class) = match line.strip_prefix('-') {
+            Some(class) => (true, class),
+            None => (false, line),
+        };
+        let (property, var_prefix, filters, name) = match class.strip_prefix("backdrop-") {
+            Some(name) => (
+                "backdrop-filter",
+                "--tw-backdrop-",
+                &BACKDROP_FILTERS[..],
+                name,
+            ),
+            None => ("filter", "--tw-", &FILTERS[..], class),
+        };
+
+        if name == "filter-none" && !is_negative {
+            self.set_plugin(property, 0);
+            self.append_declaration(line, property, "none");
+            return Some(());
         }
+
+        let (entry, function, value) =
+            filters
+                .iter()
+                .enumerate()
+                .find_map(|(entry, (function, theme_key))| {
+                    let key = if name == *function {
+                        "DEFAULT"
+                    } else {
+                        name.strip_prefix(function)?.strip_prefix('-')?
+                    };
+                    Some((
+                        entry,
+                        function,
+                        self.config.get_filter(theme_key, key)?.clone(),
+                    ))
+                })?;
+
+        if is_negative && *function != "hue-rotate" {
+            return None;
+        }
+
+        self.set_plugin(property, entry);
+        let sign = if is_negative { "-" } else { "" };
+        let value = match &value {
+            FilterValue::List(values) => values
+                .iter()
+                .map(|val| format!("{}({}{})", function, sign, val))
+                .collect::<Vec<_>>()
+                .join(" "),
+            FilterValue::Single(value) => format!("{}({}{})", function, sign, value),
+        };
+
+        let composed = filters
+            .iter()
+            .map(|(function, _)| format!("var({}{},)", var_prefix, function))
+            .collect::<Vec<_>>()
+            .join(" ");
+        self.append_declarations(
+            line,
+            vec![
+                (format!("{}{}", var_prefix, function), value),
+                (property.to_string(), composed),
+            ],
+        );
+        Some(())
+    }
+
+    pub fn generate_container(&mut self) {
+        let container = self.config.container.clone();
+        self.set_plugin("container", 0);
+        self.set_layer(Layer::Components);
+        let padding = |size: &str| {
+            vec![
+                ("padding-right".to_string(), size.to_string()),
+                ("padding-left".to_string(), size.to_string()),
+            ]
+        };
+
+        let mut declarations = vec![("width".to_string(), "100%".to_string())];
+        if container.center {
+            declarations.push(("margin-right".into(), "auto".into()));
+            declarations.push(("margin-left".into(), "auto".into()));
+        }
+        if let Some(size) = container.padding.get("DEFAULT") {
+            declarations.extend(padding(size));
+        }
+        self.append_rule(StyleRule::new(".container", declarations));
+
+        for (screen, size) in self.config.screens.clone() {
+            let mut declarations = vec![("max-width".to_string(), size.clone())];
+            if let Some(size) = container.padding.get(&screen) {
+                declarations.extend(padding(size));
+            }
+            let mut css = StyleRule::new(".container", declarations);
+            css.at_rules.push(format!("@media (min-width: {})", size));
+            self.append_rule(css);
+        }
+    }
+
+    fn append_object(&mut self, line: &str, obj: &Map<String, Value>) {
+        let selector = format!(".{}", line.escape_class_name());
+        for css in object_rules(&selector, &[], obj) {
+            self.append_rule(css);
+        }
+    }
+
+    /// Classes registered by the input stylesheet through `@layer` and
+    /// `@utility`. They are checked before the core utilities so that they
+    /// can reuse prefixes like `text-`.
+    pub fn generate_custom(&mut self, line: &str) -> Option<()> {
+        let key = format!(".{}", line);
+        let (layer, name, (entry, obj)) = match find_class(&self.config.components, &key) {
+            Some(found) => (Layer::Components, "components", found),
+            None => (
+                Layer::Utilities,
+                "utilities",
+                find_class(&self.config.custom_utilities, &key)?,
+            ),
+        };
+        let obj = obj.clone();
+        self.set_plugin(name, entry);
+        self.set_layer(layer);
+        self.append_object(line, &obj);
+        Some(())
+    }
+
+    /// A class of a family declared in `utilities`, e.g. `tab-4`, `tab` for
+    /// the `DEFAULT` value or `-tab-4` when it supports negative values.
+    pub fn generate_value_utility(&mut self, line: &str) -> Option<()> {
+        let (positive, is_negative) = match line.strip_prefix('-') {
+            Some(positive) => (positive, true),
+            None => (line, false),
+        };
+        let (entry, name, properties, value) = self
+            .config
+            .value_utilities
+            .iter()
+            .enumerate()
+            .filter(|(_, (_, utility))| !is_negative || utility.mode == Mode::WithNegative)
+            .find_map(|(entry, (name, utility))| {
+                let value = match positive.strip_prefix(name.as_str())? {
+                    "" => utility.values.get("DEFAULT")?.clone(),
+                    key => {
+                        let key = key.strip_prefix('-')?;
+                        match arbitrary_value(key) {
+                            Some(value) => value,
+                            None => utility.values.get(key)?.clone(),
+                        }
+                    }
+                };
+                Some((entry, name.clone(), utility.properties.clone(), value))
+            })?;
+        let value = match is_negative {
+            true => format!("-{}", value),
+            false => value,
+        };
+        let declarations = properties
+            .into_iter()
+            .map(|property| (property, value.clone()))
+            .collect();
+        self.set_plugin(&name, entry);
+        self.append_declarations(line, declarations);
+        Some(())
     }
 
     pub fn get_key_name(line: &str) -> (String, String, bool) {
@@ -127,29 +837,37 @@ impl Css {
 
     pub fn generate_plugin(&mut self, line: &str) -> Option<()> {
         let key = format!(".{}", line);
-        for plugin in self.config.utility.values() {
-            if let Some((attribute, value)) = Config::get_obj(plugin, &key) {
-                let css = &format!(
-                    ".{} {{\n\t{}: {};\n}}",
-                    line.escape_class_name(),
-                    attribute,
-                    value
-                );
-                self.append_css(css);
-                return Some(());
-            }
+        let found = self.config.utility.iter().find_map(|(name, plugin)| {
+            let (entry, obj) = find_class(plugin, &key)?;
+            Some((name.clone(), entry, obj.clone()))
+        });
+        if let Some((name, entry, obj)) = found {
+            self.set_plugin(&name, entry);
+            self.append_object(line, &obj);
+            return Some(());
         }
 
         let (key, name, is_negative) = Self::get_key_name(line);
-        for plugin in &self.config.plugins {
-            if let Some(css_properties) =
-                self.config
-                    .get_plugin_value(plugin, &name, &key, is_negative)
-            {
-                let css = &format!(".{} {{\n{}}}", line.escape_class_name(), css_properties);
-                self.append_css(css);
-                return Some(());
+        let found = self
+            .config
+            .plugins
+            .iter()
+            .find_map(|(plugin_name, plugin)| {
+                let css_properties =
+                    self.config
+                        .get_plugin_value(plugin, &name, &key, is_negative)?;
+                let entry = plugin.keys().position(|item| *item == name)?;
+                Some((plugin_name.clone(), entry, css_properties))
+            });
+        if let Some((plugin_name, entry, mut css_properties)) = found {
+            for (_, value) in css_properties.iter_mut() {
+                if let Some(variable) = self.spacing_variable(&key, value, is_negative) {
+                    *value = variable;
+                }
             }
+            self.set_plugin(&plugin_name, entry);
+            self.append_declarations(line, css_properties);
+            return Some(());
         }
 
         None
@@ -1,10 +1,23 @@
-use super::BufferWriter;
+use super::order::Layer;
+use super::{BufferWriter, StringBuffer, StyleRule, Variables};
 use crate::config::Config;
 use crate::generator::{Buffer, Css};
 
 use log::warn;
+use std::cell::RefCell;
 use std::collections::HashSet;
 use std::path::Path;
+use std::rc::Rc;
+
+const PREFLIGHT: &str = include_str!("../preflight.css");
+
+// class prefix -> property of the utilities resolved against the color palette.
+const COLOR_UTILITIES: [(&str, &str); 4] = [
+    ("accent", "accent-color"),
+    ("caret", "caret-color"),
+    ("fill", "fill"),
+    ("stroke", "stroke"),
+];
 
 fn handle_prefix(line: &str, prefix: &str) -> Option<String> {
     if line.starts_with(&format!("{}-", prefix)) {
@@ -14,14 +27,40 @@ fn handle_prefix(line: &str, prefix: &str) -> Option<String> {
     }
 }
 
-pub fn write_css(buffer: Box<dyn Buffer>, config: &Config, source: &HashSet<String>) {
-    let mut generator = Css::new(buffer, config.clone());
-    for line in source.iter() {
+fn generate(generator: &mut Css, source: &HashSet<String>) {
+    for class in source.iter() {
+        let utility = match generator.start_class(class) {
+            Some(utility) => utility,
+            None => continue,
+        };
+
+        // The stylesheet's own classes don't take the prefix.
+        if generator.generate_custom(&utility).is_some() {
+            continue;
+        }
+
+        let line = match generator.strip_prefix(&utility) {
+            Some(line) => line,
+            None => continue,
+        };
+        let line = line.as_str();
+
+        if generator.generate_value_utility(line).is_some() {
+            continue;
+        }
+
+        if line == "container" {
+            generator.generate_container();
+            continue;
+        }
+
         if line.starts_with("text-") {
             generator.generate_font_size(line);
             continue;
         } else if line.starts_with("font-") {
-            generator.generate_font_weight(line);
+            if generator.generate_font_family(line).is_none() {
+                generator.generate_font_weight(line);
+            }
             continue;
         }
 
@@ -30,11 +69,19 @@ pub fn write_css(buffer: Box<dyn Buffer>, config: &Config, source: &HashSet<Stri
             continue;
         }
 
-        if handle_prefix(line, "bg").is_some() {
-            generator.generate_background_color(line);
+        if handle_prefix(line, "bg").is_some() && generator.generate_background(line).is_some() {
             continue;
         }
 
+        if let Some(prefix) = ["from", "via", "to"]
+            .iter()
+            .find_map(|prefix| handle_prefix(line, prefix))
+        {
+            if generator.generate_gradient_stop(&prefix, line).is_some() {
+                continue;
+            }
+        }
+
         if handle_prefix(line, "aspect").is_some() {
             generator.generate_aspect_ratio(line);
             continue;
@@ -45,12 +92,100 @@ pub fn write_css(buffer: Box<dyn Buffer>, config: &Config, source: &HashSet<Stri
             continue;
         }
 
+        if let Some((prefix, property)) = COLOR_UTILITIES
+            .iter()
+            .find(|(prefix, _)| handle_prefix(line, prefix).is_some())
+        {
+            if generator.generate_color(prefix, property, line).is_some() {
+                continue;
+            }
+        }
+
+        if handle_prefix(line, "stroke").is_some()
+            && generator.generate_stroke_width(line).is_some()
+        {
+            continue;
+        }
+
+        if handle_prefix(line, "opacity").is_some() && generator.generate_opacity(line).is_some() {
+            continue;
+        }
+
+        if generator.generate_box_shadow(line).is_some() {
+            continue;
+        }
+
+        if handle_prefix(line, "duration").is_some()
+            && generator.generate_transition_duration(line).is_some()
+        {
+            continue;
+        }
+
+        if handle_prefix(line, "border-spacing").is_some()
+            && generator.generate_border_spacing(line).is_some()
+        {
+            continue;
+        }
+
+        if handle_prefix(line, "list").is_some() && generator.generate_list_style(line).is_some() {
+            continue;
+        }
+
+        if handle_prefix(line, "outline").is_some() && generator.generate_outline(line).is_some() {
+            continue;
+        }
+
+        if generator.generate_filter(line).is_some() {
+            continue;
+        }
+
         if generator.generate_plugin(line).is_some() {
             continue;
         }
     }
 }
 
+pub fn write_css(buffer: Box<dyn Buffer>, config: &Config, source: &HashSet<String>) {
+    let mut generator = Css::new(buffer, config.clone());
+    generate(&mut generator, source);
+    generator.write_variables();
+    generator.flush();
+}
+
+/// The CSS of a single layer, used to fill in `@tailwind` directives.
+pub fn write_layer(config: &Config, source: &HashSet<String>, layer: Layer) -> String {
+    if layer == Layer::Base {
+        return match config.preflight {
+            true => PREFLIGHT.trim_end().to_string(),
+            false => String::new(),
+        };
+    }
+
+    let output = Rc::new(RefCell::new(String::new()));
+    let mut generator = Css::new(Box::new(StringBuffer::new(output.clone())), config.clone());
+    generate(&mut generator, source);
+    generator.flush_layer(layer);
+    let css = output.borrow().trim_end().to_string();
+    css
+}
+
+/// The rules generated for a single class, used to expand `@apply`, and
+/// the theme variables they reference.
+pub fn class_rules(config: &Config, class: &str) -> (Vec<StyleRule>, Variables) {
+    let output = Rc::new(RefCell::new(String::new()));
+    let mut generator = Css::new(Box::new(StringBuffer::new(output)), config.clone());
+    generate(&mut generator, &HashSet::from([class.to_string()]));
+    (generator.take_rules(|_| true), generator.take_variables())
+}
+
+/// The theme variables referenced by the classes of `source`.
+pub fn theme_variables(config: &Config, source: &HashSet<String>) -> Variables {
+    let output = Rc::new(RefCell::new(String::new()));
+    let mut generator = Css::new(Box::new(StringBuffer::new(output)), config.clone());
+    generate(&mut generator, source);
+    generator.take_variables()
+}
+
 pub fn execute(source: &HashSet<String>, output: &str, config_json: &Config) {
     let css_file = std::fs::OpenOptions::new()
         .write(true)
@@ -60,7 +195,10 @@ pub fn execute(source: &HashSet<String>, output: &str, config_json: &Config) {
         .open(Path::new(&output));
 
     if let Ok(file) = css_file {
-        let buffer = BufferWriter::new(file.try_clone().unwrap());
+        let mut buffer = BufferWriter::new(file.try_clone().unwrap());
+        if config_json.preflight {
+            buffer.write(PREFLIGHT.trim_end());
+        }
         write_css(Box::new(buffer), config_json, source);
     } else {
         warn!("Unable to create file: {}", output);
@@ -1,10 +1,23 @@
 mod buffer;
+mod color;
 mod css;
 mod generate;
+mod order;
+mod rule;
+mod safelist;
 mod utils;
+mod variables;
 
 pub use buffer::Buffer;
 pub use buffer::BufferWriter;
+pub use buffer::StringBuffer;
+pub use color::with_alpha;
 pub use css::Css;
-pub use generate::execute;
+#[cfg(test)]
 pub use generate::write_css;
+pub use generate::{class_rules, execute, theme_variables, write_layer};
+pub use order::Layer;
+pub use rule::StyleRule;
+pub use safelist::select_classes;
+pub use utils::EscapeClassName;
+pub use variables::Variables;
@@ -0,0 +1,36 @@
+use crate::config::CORE_PLUGINS;
+use std::cmp::Reverse;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Layer {
+    Base,
+    Components,
+    Utilities,
+}
+
+/// Position of a plugin in the emit order; unknown plugins go last.
+pub fn plugin_order(name: &str) -> usize {
+    CORE_PLUGINS
+        .iter()
+        .position(|plugin| *plugin == name)
+        .unwrap_or(CORE_PLUGINS.len())
+}
+
+/// Sort key of the rules generated for one class. Rules are ordered by
+/// layer, then variants (plain utilities first, so that `sm:m-2` still wins
+/// over `mt-4`), then plugin, the utility's position inside its plugin,
+/// declaration count (more first, so shorthands come before the longhands
+/// that refine them) and finally the utility value, which makes the output
+/// the same on every run.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct SortKey {
+    pub layer: Layer,
+    // Variant registration indexes, highest first, so that every rule of a
+    // variant sorts after the plain rules and before the next variant.
+    pub variants: Vec<usize>,
+    pub plugin: usize,
+    pub entry: usize,
+    pub properties: Reverse<usize>,
+    pub utility: String,
+    pub class: String,
+}
@@ -0,0 +1,35 @@
+use std::fmt;
+
+/// A generated style rule, e.g. `.p-4 { padding: 1rem; }`, nested inside
+/// `at_rules` from the outermost one in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StyleRule {
+    pub at_rules: Vec<String>,
+    pub selector: String,
+    pub declarations: Vec<(String, String)>,
+}
+
+impl StyleRule {
+    pub fn new(selector: &str, declarations: Vec<(String, String)>) -> Self {
+        Self {
+            at_rules: Vec::new(),
+            selector: selector.to_string(),
+            declarations,
+        }
+    }
+}
+
+impl fmt::Display for StyleRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut css = format!("{} {{\n", self.selector);
+        for (property, value) in &self.declarations {
+            css.push_str(&format!("\t{}: {};\n", property, value));
+        }
+        css.push('}');
+        for at_rule in self.at_rules.iter().rev() {
+            let indented: Vec<String> = css.lines().map(|l| format!("\t{}", l)).collect();
+            css = format!("{} {{\n{}\n}}", at_rule, indented.join("\n"));
+        }
+        f.write_str(&css)
+    }
+}
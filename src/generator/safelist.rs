@@ -0,0 +1,159 @@
+use crate::config::{ClassPattern, Color, Config, Mode, SafelistEntry, BACKDROP_FILTERS, FILTERS};
+use serde_json::Value;
+use std::collections::HashSet;
+
+// Prefixes of the utilities resolved against the color palette.
+const COLOR_PREFIXES: [&str; 9] = [
+    "bg", "from", "via", "to", "accent", "caret", "fill", "stroke", "outline",
+];
+
+// `{prefix}-{key}` for every key of a scale, `{prefix}` for `DEFAULT`.
+fn push_scale<'a>(classes: &mut Vec<String>, prefix: &str, keys: impl Iterator<Item = &'a String>) {
+    for key in keys {
+        match key.as_str() {
+            "DEFAULT" => classes.push(prefix.to_string()),
+            _ => classes.push(format!("{}-{}", prefix, key)),
+        }
+    }
+}
+
+// `.class` keys of a section of style objects.
+fn push_objects<'a>(classes: &mut Vec<String>, keys: impl Iterator<Item = &'a String>) {
+    classes.extend(keys.filter_map(|key| Some(key.strip_prefix('.')?.to_string())));
+}
+
+/// Utility classes the config could generate, without prefix or variants.
+/// Safelist patterns are matched against them, so a few that don't generate
+/// anything are fine.
+fn candidates(config: &Config) -> Vec<String> {
+    let mut classes = vec!["container".to_string()];
+    push_objects(&mut classes, config.components.keys());
+    push_objects(&mut classes, config.custom_utilities.keys());
+    for section in config.utility.values() {
+        push_objects(&mut classes, section.keys());
+    }
+    for section in config.break_point.values() {
+        push_objects(&mut classes, section.keys());
+    }
+
+    push_scale(&mut classes, "text", config.font_size.keys());
+    push_scale(&mut classes, "font", config.font_weight.keys());
+    push_scale(&mut classes, "font", config.font_family.keys());
+    push_scale(&mut classes, "leading", config.line_height.keys());
+    push_scale(&mut classes, "aspect", config.aspect_ratio.keys());
+    push_scale(&mut classes, "bg", config.background_image.keys());
+    push_scale(&mut classes, "bg", config.background_size.keys());
+    push_scale(&mut classes, "bg", config.background_position.keys());
+    for stop in ["from", "via", "to"] {
+        push_scale(
+            &mut classes,
+            stop,
+            config.gradient_color_stop_positions.keys(),
+        );
+    }
+    push_scale(&mut classes, "stroke", config.stroke_width.keys());
+    push_scale(&mut classes, "opacity", config.opacity.keys());
+    push_scale(&mut classes, "shadow", config.box_shadow.keys());
+    push_scale(&mut classes, "duration", config.transition_duration.keys());
+    push_scale(&mut classes, "list", config.list_style_type.keys());
+    push_scale(&mut classes, "list-image", config.list_style_image.keys());
+    push_scale(&mut classes, "outline", config.outline_width.keys());
+    push_scale(&mut classes, "outline-offset", config.outline_offset.keys());
+    for prefix in ["border-spacing", "border-spacing-x", "border-spacing-y"] {
+        push_scale(&mut classes, prefix, config.spacing.keys());
+    }
+
+    let colors: Vec<String> = config
+        .color
+        .iter()
+        .flat_map(|(name, color)| match color {
+            Color::Single(_) => vec![name.clone()],
+            Color::Palette(shades) => shades
+                .keys()
+                .map(|shade| format!("{}-{}", name, shade))
+                .collect(),
+        })
+        .collect();
+    for prefix in COLOR_PREFIXES {
+        push_scale(&mut classes, prefix, colors.iter());
+    }
+
+    classes.push("filter-none".to_string());
+    classes.push("backdrop-filter-none".to_string());
+    for (filters, prefix) in [(&FILTERS, ""), (&BACKDROP_FILTERS, "backdrop-")] {
+        for (function, key) in filters.iter() {
+            if let Some(scale) = config.filter.get(*key) {
+                push_scale(
+                    &mut classes,
+                    &format!("{}{}", prefix, function),
+                    scale.keys(),
+                );
+            }
+        }
+    }
+    if let Some(scale) = config.filter.get("hueRotate") {
+        push_scale(&mut classes, "-hue-rotate", scale.keys());
+        push_scale(&mut classes, "-backdrop-hue-rotate", scale.keys());
+    }
+
+    for (name, utility) in &config.value_utilities {
+        push_scale(&mut classes, name, utility.values.keys());
+        if utility.mode == Mode::WithNegative {
+            push_scale(&mut classes, &format!("-{}", name), utility.values.keys());
+        }
+    }
+
+    // Plugin values come from their own theme section or the spacing scale.
+    for (name, plugin) in &config.plugins {
+        let section = config.theme.get(name).and_then(Value::as_object);
+        let keys: Vec<&String> = config
+            .spacing
+            .keys()
+            .chain(section.into_iter().flat_map(|section| section.keys()))
+            .collect();
+        for class in plugin.keys() {
+            push_scale(&mut classes, class, keys.iter().copied());
+        }
+    }
+    classes
+}
+
+/// `source` with the classes of the safelist added and the ones of the
+/// blocklist removed. Patterns match anywhere in the class name unless they
+/// are anchored with `^` and `$`.
+pub fn select_classes(config: &Config, source: &HashSet<String>) -> HashSet<String> {
+    let mut classes = source.clone();
+    let mut matchable: Option<Vec<String>> = None;
+    for entry in &config.safelist {
+        let (pattern, variants) = match entry {
+            SafelistEntry::Class(ClassPattern::Class(class)) => {
+                classes.insert(class.clone());
+                continue;
+            }
+            SafelistEntry::Class(ClassPattern::Regex(pattern)) => (pattern, &[][..]),
+            SafelistEntry::Pattern { pattern, variants } => (pattern, &variants[..]),
+        };
+        let candidates = matchable.get_or_insert_with(|| {
+            candidates(config)
+                .into_iter()
+                .map(|class| match class.strip_prefix('-') {
+                    Some(class) => format!("-{}{}", config.prefix, class),
+                    None => format!("{}{}", config.prefix, class),
+                })
+                .collect()
+        });
+        for class in candidates.iter().filter(|class| pattern.is_match(class)) {
+            classes.insert(class.clone());
+            for variant in variants {
+                classes.insert(format!("{}{}{}", variant, config.separator, class));
+            }
+        }
+    }
+    classes.retain(|class| {
+        !config
+            .blocklist
+            .iter()
+            .any(|pattern| pattern.matches(class))
+    });
+    classes
+}
@@ -4,12 +4,88 @@ pub trait EscapeClassName {
 
 impl EscapeClassName for str {
     fn escape_class_name(&self) -> String {
-        self.replace(".", "\\.").replace("/", "\\/")
+        let mut escaped = String::with_capacity(self.len());
+        for c in self.chars() {
+            if !(c.is_ascii_alphanumeric() || c == '-' || c == '_' || !c.is_ascii()) {
+                escaped.push('\\');
+            }
+            escaped.push(c);
+        }
+        escaped
     }
 }
 
 impl EscapeClassName for String {
     fn escape_class_name(&self) -> String {
-        self.replace(".", "\\.").replace("/", "\\/")
+        self.as_str().escape_class_name()
+    }
+}
+
+/// Value of an arbitrary class key such as `[url(/img/hero.png)]`, with
+/// underscores turned back into spaces outside of `url(...)`.
+pub fn arbitrary_value(key: &str) -> Option<String> {
+    let value = key.strip_prefix('[')?.strip_suffix(']')?;
+    if value.is_empty() {
+        return None;
+    }
+    let mut output = String::with_capacity(value.len());
+    // Parentheses open in the current `url(...)`.
+    let mut url_depth = 0;
+    for (index, c) in value.char_indices() {
+        match c {
+            '(' if url_depth > 0 || value[..index].ends_with("url") => url_depth += 1,
+            ')' if url_depth > 0 => url_depth -= 1,
+            '_' if url_depth == 0 => {
+                output.push(' ');
+                continue;
+            }
+            _ => {}
+        }
+        output.push(c);
+    }
+    Some(output)
+}
+
+const LENGTH_UNITS: [&str; 18] = [
+    "", "px", "rem", "em", "%", "vh", "vw", "vmin", "vmax", "dvh", "svh", "lvh", "ch", "ex", "pt",
+    "pc", "cm", "mm",
+];
+
+/// Whether an arbitrary value is a plain number or a CSS length such as
+/// `3px` or `.5rem`, as opposed to a color.
+pub fn is_length(value: &str) -> bool {
+    let unit_start = value
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(unit_start);
+    number.parse::<f64>().is_ok() && LENGTH_UNITS.contains(&unit)
+}
+
+/// Split `md:hover:bg-[url(a:b)]` into its variants and the utility,
+/// ignoring separators inside arbitrary values.
+pub fn split_variants<'a>(class: &'a str, separator: &str) -> (Vec<&'a str>, &'a str) {
+    let mut variants = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+    for (index, c) in class.char_indices() {
+        match c {
+            '[' | '(' => depth += 1,
+            ']' | ')' => depth -= 1,
+            _ if depth == 0 && index >= start && class[index..].starts_with(separator) => {
+                variants.push(&class[start..index]);
+                start = index + separator.len();
+            }
+            _ => {}
+        }
+    }
+    (variants, &class[start..])
+}
+
+/// The utility of a class without the configured prefix, e.g. `mt-4` for
+/// `tw-mt-4` and `-mt-4` for `-tw-mt-4`. `None` when the prefix is missing.
+pub fn strip_class_prefix(utility: &str, prefix: &str) -> Option<String> {
+    match utility.strip_prefix('-') {
+        Some(utility) => Some(format!("-{}", utility.strip_prefix(prefix)?)),
+        None => Some(utility.strip_prefix(prefix)?.to_string()),
     }
 }
@@ -0,0 +1,33 @@
+use super::rule::StyleRule;
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// Theme values that the generated CSS references as custom properties,
+/// e.g. `--color-blue-500`. Kept in theme order: by namespace, then by the
+/// position of the key in the config.
+#[derive(Debug, Clone, Default)]
+pub struct Variables {
+    tokens: BTreeMap<Vec<usize>, (String, String)>,
+}
+
+impl Variables {
+    pub fn insert(&mut self, order: Vec<usize>, name: &str, value: &str) {
+        self.tokens
+            .insert(order, (name.to_string(), value.to_string()));
+    }
+
+    pub fn extend(&mut self, other: Variables) {
+        self.tokens.extend(other.tokens);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tokens.is_empty()
+    }
+}
+
+impl fmt::Display for Variables {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let declarations = self.tokens.values().cloned().collect();
+        write!(f, "{}", StyleRule::new(":root", declarations))
+    }
+}
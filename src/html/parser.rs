@@ -22,13 +22,9 @@ impl Css {
     }
 
     pub fn push(&mut self, class: &str) {
-        class
-            .to_owned()
-            .split_whitespace()
-            .into_iter()
-            .for_each(|val| {
-                self.source.insert(val.to_string());
-            });
+        class.split_whitespace().for_each(|val| {
+            self.source.insert(val.to_string());
+        });
     }
 }
 
@@ -37,7 +33,7 @@ fn collect_css(node: &Handle, css: &mut Css) {
         NodeData::Element { ref attrs, .. } => {
             for attr in attrs.borrow().iter() {
                 if &*attr.name.local == "class" {
-                    css.push(&*attr.value);
+                    css.push(&attr.value);
                 }
             }
         }
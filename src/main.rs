@@ -3,22 +3,67 @@ extern crate notify;
 mod config;
 mod generator;
 mod html;
+mod stylesheet;
 
 #[cfg(test)]
 mod test;
 
-use clap::Parser;
-use log::{info, warn};
+use clap::{Parser, Subcommand};
+use config::Config;
+use env_logger::Env;
+use log::{error, info, warn};
 use notify::{DebouncedEvent, RecommendedWatcher, RecursiveMode, Watcher};
+use std::collections::HashSet;
 use std::path::Path;
 use std::sync::mpsc::channel;
 use std::time::Duration;
 
-fn watch(source: &str, output: &str, should_watch: bool) -> notify::Result<()> {
-    let default = include_str!("default-config.json");
-    let config = config::parse(default).unwrap();
-    let css = html::parse(Path::new(&source)).unwrap();
-    generator::execute(&css, output, &config);
+fn build(
+    source: &HashSet<String>,
+    output: &str,
+    stylesheet: Option<&str>,
+    config: &Config,
+) -> Result<(), stylesheet::Error> {
+    let source = &generator::select_classes(config, source);
+    match stylesheet {
+        Some(input) => stylesheet::execute(input, source, output, config),
+        None => {
+            generator::execute(source, output, config);
+            Ok(())
+        }
+    }
+}
+
+fn watch(
+    source: &str,
+    output: &str,
+    stylesheet: Option<&str>,
+    config_path: Option<&Path>,
+    should_watch: bool,
+    preflight: bool,
+    css_variables: bool,
+) -> notify::Result<()> {
+    let config_path = config_path.map(Path::to_path_buf).or_else(config::discover);
+    let mut config = match config::load(config_path.as_deref()) {
+        Ok(config) => config,
+        Err(e) => {
+            error!("{}", e);
+            std::process::exit(1);
+        }
+    };
+    if let Some(path) = &config_path {
+        info!("Using config: {}", path.display());
+        for diagnostic in &config.diagnostics {
+            warn!("{}: {}", path.display(), diagnostic);
+        }
+    }
+    config.preflight = config.preflight && preflight;
+    config.css_variables = css_variables;
+    let mut css = html::parse(Path::new(&source)).unwrap();
+    if let Err(e) = build(&css, output, stylesheet, &config) {
+        error!("{}", e);
+        std::process::exit(1);
+    }
 
     info!("CSS generated: {}", output);
     if !should_watch {
@@ -28,9 +73,15 @@ fn watch(source: &str, output: &str, should_watch: bool) -> notify::Result<()> {
     let (tx, rx) = channel();
     let mut watcher: RecommendedWatcher = Watcher::new(tx, Duration::from_millis(500))?;
     watcher.watch(Path::new(&source), RecursiveMode::NonRecursive)?;
+    if let Some(input) = stylesheet {
+        watcher.watch(Path::new(input), RecursiveMode::NonRecursive)?;
+    }
 
     info!("Start watching file: {}", &source);
 
+    // notify reports absolute paths, the stylesheet may be relative.
+    let stylesheet_path = stylesheet.and_then(|input| Path::new(input).canonicalize().ok());
+
     loop {
         let event = match rx.recv() {
             Ok(event) => event,
@@ -45,9 +96,19 @@ fn watch(source: &str, output: &str, should_watch: bool) -> notify::Result<()> {
             | DebouncedEvent::Write(path)
             | DebouncedEvent::Create(path)
             | DebouncedEvent::Chmod(path) => {
-                let css = html::parse(&path).unwrap();
-                generator::execute(&css, output, &config);
-                info!("CSS {} updated", output);
+                if stylesheet_path.is_none() || path.canonicalize().ok() != stylesheet_path {
+                    css = match html::parse(&path) {
+                        Ok(css) => css,
+                        Err(e) => {
+                            error!("{}: {}", path.display(), e);
+                            continue;
+                        }
+                    };
+                }
+                match build(&css, output, stylesheet, &config) {
+                    Ok(()) => info!("CSS {} updated", output),
+                    Err(e) => error!("{}", e),
+                }
             }
             _ => (),
         }
@@ -55,29 +116,109 @@ fn watch(source: &str, output: &str, should_watch: bool) -> notify::Result<()> {
     Ok(())
 }
 
+/// Write a config with the theme imported from a design tokens file.
+fn import_tokens(tokens: &Path, output: &Path, force: bool) -> Result<(), String> {
+    if output.exists() && !force {
+        return Err(format!(
+            "{} already exists, pass --force to overwrite it",
+            output.display()
+        ));
+    }
+    let theme = config::read_tokens(tokens).map_err(|e| e.to_string())?;
+    let config = serde_json::json!({ "theme": { "extend": theme } });
+    let source = match output.extension().and_then(|extension| extension.to_str()) {
+        Some("toml") => toml::to_string_pretty(&config).map_err(|e| e.to_string())?,
+        _ => serde_json::to_string_pretty(&config).map_err(|e| e.to_string())? + "\n",
+    };
+    std::fs::write(output, source).map_err(|e| format!("{}: {}", output.display(), e))
+}
+
 /// Lightweight tailwindcss!
 #[derive(Parser, Debug)]
 #[clap(author, version, about, long_about = None)]
+#[clap(subcommand_negates_reqs = true)]
 struct Application {
+    #[clap(subcommand)]
+    command: Option<Command>,
+
     /// Source directories for html files!
-    #[clap(short, long)]
-    input: String,
+    #[clap(short, long, required = true)]
+    input: Option<String>,
 
     /// CSS output file path
+    #[clap(short, long, required = true)]
+    output: Option<String>,
+
+    /// Input CSS file with `@tailwind` directives
+    #[clap(long)]
+    css: Option<String>,
+
+    /// Config file (JSON, JSON5 or TOML) merged over the defaults, `tailwind.config.json` if present
     #[clap(short, long)]
-    output: String,
+    config: Option<String>,
 
     /// Enable watch file changes
     #[clap(short, long)]
     watch: bool,
+
+    /// Skip the Preflight base styles
+    #[clap(long)]
+    no_preflight: bool,
+
+    /// Reference theme values through CSS variables declared in `:root`
+    #[clap(long)]
+    css_variables: bool,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Write a config file with the theme of a W3C design tokens (DTCG) file
+    Tokens {
+        /// Design tokens file
+        tokens: String,
+
+        /// Config file to write, TOML if it ends in `.toml` and JSON otherwise
+        #[clap(short, long, default_value = "tailwind.config.json")]
+        output: String,
+
+        /// Overwrite the config file if it exists
+        #[clap(long)]
+        force: bool,
+    },
 }
 
 fn main() {
-    env_logger::init();
+    env_logger::Builder::from_env(Env::default().default_filter_or("warn")).init();
 
     let args = Application::parse();
 
-    if let Err(e) = watch(&args.input, &args.output, args.watch) {
+    if let Some(Command::Tokens {
+        tokens,
+        output,
+        force,
+    }) = &args.command
+    {
+        match import_tokens(Path::new(tokens), Path::new(output), *force) {
+            Ok(()) => info!("Config written to {}", output),
+            Err(e) => {
+                error!("{}", e);
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+
+    // Both are required when no subcommand is given.
+    let (input, output) = (args.input.unwrap(), args.output.unwrap());
+    if let Err(e) = watch(
+        &input,
+        &output,
+        args.css.as_deref(),
+        args.config.as_deref().map(Path::new),
+        args.watch,
+        !args.no_preflight,
+        args.css_variables,
+    ) {
         warn!("error: {:?}", e);
         std::process::exit(1);
     }
@@ -0,0 +1,62 @@
+use std::fmt;
+
+#[derive(Debug)]
+pub enum Error {
+    Io(String, std::io::Error),
+    UnknownClass {
+        file: String,
+        line: usize,
+        class: String,
+    },
+    MissingDirective {
+        file: String,
+        line: usize,
+        layer: String,
+    },
+    Function {
+        file: String,
+        line: usize,
+        message: String,
+    },
+    Nesting {
+        file: String,
+        line: usize,
+        selector: String,
+    },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(file, e) => write!(f, "{}: {}", file, e),
+            Error::UnknownClass { file, line, class } => {
+                write!(
+                    f,
+                    "{}:{}: cannot `@apply` unknown class `{}`",
+                    file, line, class
+                )
+            }
+            Error::MissingDirective { file, line, layer } => write!(
+                f,
+                "{}:{}: `@layer {}` is used but no matching `@tailwind {}` directive is present",
+                file, line, layer, layer
+            ),
+            Error::Function {
+                file,
+                line,
+                message,
+            } => write!(f, "{}:{}: {}", file, line, message),
+            Error::Nesting {
+                file,
+                line,
+                selector,
+            } => write!(
+                f,
+                "{}:{}: cannot `@apply` rules for `{}`, it is already a declaration",
+                file, line, selector
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
@@ -0,0 +1,126 @@
+use super::parser::{line_of, line_starts};
+use super::Error;
+use crate::config::Config;
+use crate::generator::with_alpha;
+
+const FUNCTIONS: [&str; 3] = ["theme(", "--spacing(", "screen("];
+
+fn is_ident(c: u8) -> bool {
+    c.is_ascii_alphanumeric() || c == b'-' || c == b'_'
+}
+
+// Offset of the `)` closing the call whose arguments start at `start`.
+fn closing_paren(input: &str, start: usize) -> Option<usize> {
+    let mut depth = 0;
+    for (i, c) in input[start..].char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' if depth == 0 => return Some(start + i),
+            ')' => depth -= 1,
+            _ => {}
+        }
+    }
+    None
+}
+
+fn theme(config: &Config, args: &str) -> Result<String, String> {
+    let path = args.trim_matches(|c| c == '"' || c == '\'');
+    let error = match config.get_theme(path) {
+        Ok(value) => return Ok(value),
+        Err(error) => error,
+    };
+    // `colors.blue.500 / 50%`, unless the slash is part of a key like `1/2`.
+    let (path, alpha) = path.rsplit_once('/').ok_or(error.clone())?;
+    let color = config.get_theme(path.trim()).map_err(|_| error)?;
+    with_alpha(&color, alpha.trim()).ok_or(format!(
+        "'{}' is not a hex or oklch color and can't take an opacity modifier",
+        path.trim()
+    ))
+}
+
+fn spacing(config: &Config, args: &str) -> Result<String, String> {
+    if let Ok(value) = config.get_theme(&format!("spacing.{}", args)) {
+        return Ok(value);
+    }
+    if args.parse::<f64>().is_err() {
+        return Err(format!("--spacing() expects a number, got '{}'", args));
+    }
+    Ok(format!(
+        "calc({} * {})",
+        config.get_theme("spacing.1")?,
+        args
+    ))
+}
+
+fn screen(config: &Config, args: &str) -> Result<String, String> {
+    match config.screens.get(args) {
+        Some(size) => Ok(format!("(min-width: {})", size)),
+        None => Err(format!(
+            "'{}' is not a screen, use one of: {}",
+            args,
+            config
+                .screens
+                .keys()
+                .cloned()
+                .collect::<Vec<_>>()
+                .join(", ")
+        )),
+    }
+}
+
+/// Replace the `theme()`, `--spacing()` and `screen()` calls of `input` with
+/// their values. Line breaks inside a call are kept after its value, so later
+/// errors still point at the right line.
+pub fn resolve(input: &str, file: &str, config: &Config) -> Result<String, Error> {
+    let bytes = input.as_bytes();
+    let mut output = String::with_capacity(input.len());
+    let mut copied = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        if input[i..].starts_with("/*") {
+            i = input[i + 2..]
+                .find("*/")
+                .map_or(bytes.len(), |end| i + end + 4);
+            continue;
+        }
+        if bytes[i] == b'"' || bytes[i] == b'\'' {
+            let quote = bytes[i] as char;
+            i = input[i + 1..]
+                .find(quote)
+                .map_or(bytes.len(), |end| i + end + 2);
+            continue;
+        }
+
+        let function = FUNCTIONS.iter().find(|name| input[i..].starts_with(*name));
+        let start = i;
+        i += input[i..].chars().next().map_or(1, char::len_utf8);
+        let function = match function {
+            Some(function) if start == 0 || !is_ident(bytes[start - 1]) => function,
+            _ => continue,
+        };
+        let args_start = start + function.len();
+        let end = match closing_paren(input, args_start) {
+            Some(end) => end,
+            None => continue,
+        };
+
+        let args = input[args_start..end].trim();
+        let value = match *function {
+            "theme(" => theme(config, args),
+            "--spacing(" => spacing(config, args),
+            _ => screen(config, args),
+        };
+        let value = value.map_err(|message| Error::Function {
+            file: file.to_string(),
+            line: line_of(&line_starts(input), start),
+            message,
+        })?;
+        output.push_str(&input[copied..start]);
+        output.push_str(&value);
+        output.push_str(&"\n".repeat(input[start..end].matches('\n').count()));
+        copied = end + 1;
+        i = end + 1;
+    }
+    output.push_str(&input[copied..]);
+    Ok(output)
+}
@@ -0,0 +1,426 @@
+mod error;
+mod functions;
+mod parser;
+
+pub use error::Error;
+
+use crate::config::Config;
+use crate::generator::{
+    class_rules, theme_variables, write_layer, EscapeClassName, Layer, StyleRule, Variables,
+};
+use parser::{Node, NodeKind};
+
+use serde_json::{Map, Value};
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+use std::path::Path;
+
+// Replacement text for a span of the input.
+type Edit = (Range<usize>, String);
+
+// The layers an `@tailwind` directive or `@import` stands for.
+fn directive_layers(node: &Node) -> Option<Vec<Layer>> {
+    let (name, params) = match &node.kind {
+        NodeKind::AtRule {
+            name,
+            params,
+            block: None,
+        } => (
+            name.as_str(),
+            params.trim_matches(|c| c == '"' || c == '\''),
+        ),
+        _ => return None,
+    };
+    match (name, params) {
+        ("tailwind", "base") => Some(vec![Layer::Base]),
+        ("tailwind", "components") => Some(vec![Layer::Components]),
+        ("tailwind", "utilities") => Some(vec![Layer::Utilities]),
+        ("import", "tailwindcss") => Some(vec![Layer::Base, Layer::Components, Layer::Utilities]),
+        ("import", "tailwindcss/preflight") => Some(vec![Layer::Base]),
+        ("import", "tailwindcss/utilities") => Some(vec![Layer::Utilities]),
+        _ => None,
+    }
+}
+
+fn layer_name(name: &str) -> Option<Layer> {
+    match name {
+        "base" => Some(Layer::Base),
+        "components" => Some(Layer::Components),
+        "utilities" => Some(Layer::Utilities),
+        _ => None,
+    }
+}
+
+// Whitespace in front of `offset` on its line, to indent inserted lines.
+fn indentation(input: &str, offset: usize) -> &str {
+    let line_start = input[..offset].rfind('\n').map_or(0, |i| i + 1);
+    let prefix = &input[line_start..offset];
+    &prefix[..prefix.len() - prefix.trim_start().len()]
+}
+
+// Span to remove a statement with. A statement on a line of its own takes
+// its indentation and line break along so that no blank line is left behind.
+fn removal_span(input: &str, span: &Range<usize>) -> Range<usize> {
+    let line_start = input[..span.start].rfind('\n').map_or(0, |i| i + 1);
+    if !input[line_start..span.start].trim_start().is_empty() {
+        return span.clone();
+    }
+    match input[span.end..].starts_with('\n') {
+        true => line_start..span.end + 1,
+        false => line_start..span.end,
+    }
+}
+
+// Split `.card:hover` into the class `card` and the rest of the selector.
+// `None` for selectors that don't start with a plain class.
+fn split_class_selector(selector: &str) -> Option<(&str, &str)> {
+    let selector = selector.strip_prefix('.')?;
+    let end = selector
+        .find(|c: char| !(c.is_alphanumeric() || c == '-' || c == '_'))
+        .unwrap_or(selector.len());
+    let (class, rest) = selector.split_at(end);
+    if class.is_empty() || rest.contains(',') || rest.starts_with('\\') {
+        return None;
+    }
+    Some((class, rest))
+}
+
+// Insert `obj` under `key`, merging with what is already there.
+fn merge_object(map: &mut Map<String, Value>, key: &str, obj: Map<String, Value>) {
+    match map.get_mut(key).and_then(Value::as_object_mut) {
+        Some(existing) => existing.extend(obj),
+        None => {
+            map.insert(key.to_string(), Value::Object(obj));
+        }
+    }
+}
+
+struct Stylesheet<'a> {
+    input: &'a str,
+    file: &'a str,
+    config: Config,
+    source: &'a HashSet<String>,
+    // CSS from `@layer` blocks that is always emitted with its layer.
+    layers: HashMap<Layer, Vec<String>>,
+    // Directives are filled in last, once `@apply` has added its variables.
+    directives: Vec<(Range<usize>, Vec<Layer>)>,
+    variables: Variables,
+    edits: Vec<Edit>,
+}
+
+impl<'a> Stylesheet<'a> {
+    // Take the `@layer` and `@utility` blocks out of the stylesheet and
+    // register their classes with the config.
+    fn register(&mut self, nodes: &[Node]) -> Result<(), Error> {
+        let directives: HashSet<Layer> = nodes
+            .iter()
+            .filter_map(directive_layers)
+            .flatten()
+            .collect();
+        for node in nodes {
+            match &node.kind {
+                NodeKind::AtRule {
+                    name,
+                    params,
+                    block: Some(block),
+                } if name == "layer" => {
+                    let layer = match layer_name(params) {
+                        Some(layer) => layer,
+                        None => continue,
+                    };
+                    if !directives.contains(&layer) {
+                        return Err(Error::MissingDirective {
+                            file: self.file.to_string(),
+                            line: node.line,
+                            layer: params.to_string(),
+                        });
+                    }
+                    self.register_layer(layer, block)?;
+                }
+                NodeKind::AtRule {
+                    name,
+                    params,
+                    block: Some(block),
+                } if name == "utility" => {
+                    let obj = self.block_object(block)?;
+                    let key = format!(".{}", params);
+                    merge_object(&mut self.config.custom_utilities, &key, obj);
+                }
+                _ => continue,
+            }
+            self.edits
+                .push((removal_span(self.input, &node.span), String::new()));
+        }
+        Ok(())
+    }
+
+    fn register_layer(&mut self, layer: Layer, block: &[Node]) -> Result<(), Error> {
+        for node in block {
+            let class = match (&node.kind, layer) {
+                (NodeKind::Comment, _) => continue,
+                (_, Layer::Base) => None,
+                (NodeKind::Rule { selector, block }, _) => {
+                    split_class_selector(selector).map(|class| (class, block))
+                }
+                _ => None,
+            };
+            let ((class, rest), block) = match class {
+                Some(class) => class,
+                None => {
+                    let css = self.input[node.span.clone()].to_string();
+                    self.layers.entry(layer).or_default().push(css);
+                    continue;
+                }
+            };
+
+            let mut obj = self.block_object(block)?;
+            if !rest.is_empty() {
+                let mut nested = Map::new();
+                nested.insert(format!("&{}", rest), Value::Object(obj));
+                obj = nested;
+            }
+            let section = match layer {
+                Layer::Components => &mut self.config.components,
+                _ => &mut self.config.custom_utilities,
+            };
+            merge_object(section, &format!(".{}", class), obj);
+        }
+        Ok(())
+    }
+
+    // The style object of a custom class, in the format of the utility
+    // sections of the config.
+    fn block_object(&mut self, block: &[Node]) -> Result<Map<String, Value>, Error> {
+        let mut obj = Map::new();
+        for node in block {
+            match &node.kind {
+                NodeKind::Declaration { property, value } => {
+                    obj.insert(property.clone(), Value::String(value.clone()));
+                }
+                NodeKind::Rule { selector, block } => {
+                    let key = match selector.contains('&') {
+                        true => selector.clone(),
+                        false => format!("& {}", selector),
+                    };
+                    merge_object(&mut obj, &key, self.block_object(block)?);
+                }
+                NodeKind::AtRule { name, params, .. } if name == "apply" => {
+                    for class in params.split_whitespace() {
+                        for css in self.apply_rules(node, class)? {
+                            let base = format!(".{}", class.escape_class_name());
+                            let mut target = &mut obj;
+                            let selector = css.selector.replace(&base, "&");
+                            for key in css.at_rules.iter().chain([&selector]) {
+                                if key == "&" {
+                                    break;
+                                }
+                                let entry = target
+                                    .entry(key.clone())
+                                    .or_insert_with(|| Value::Object(Map::new()));
+                                target = match entry {
+                                    Value::Object(entry) => entry,
+                                    _ => {
+                                        return Err(Error::Nesting {
+                                            file: self.file.to_string(),
+                                            line: node.line,
+                                            selector: key.clone(),
+                                        })
+                                    }
+                                };
+                            }
+                            for (property, value) in css.declarations {
+                                target.insert(property, Value::String(value));
+                            }
+                        }
+                    }
+                }
+                NodeKind::AtRule {
+                    name,
+                    params,
+                    block: Some(block),
+                } => {
+                    let key = format!("@{} {}", name, params);
+                    merge_object(&mut obj, &key, self.block_object(block)?);
+                }
+                _ => {}
+            }
+        }
+        Ok(obj)
+    }
+
+    fn apply_rules(&mut self, node: &Node, class: &str) -> Result<Vec<StyleRule>, Error> {
+        let (rules, variables) = class_rules(&self.config, class);
+        self.variables.extend(variables);
+        if rules.is_empty() {
+            return Err(Error::UnknownClass {
+                file: self.file.to_string(),
+                line: node.line,
+                class: class.to_string(),
+            });
+        }
+        Ok(rules)
+    }
+
+    fn visit(&mut self, nodes: &[Node]) -> Result<(), Error> {
+        for node in nodes {
+            if let Some(layers) = directive_layers(node) {
+                self.directives.push((node.span.clone(), layers));
+                continue;
+            }
+
+            match &node.kind {
+                NodeKind::Rule { selector, block } => {
+                    self.expand_apply(node, selector, block)?;
+                    self.visit(block)?;
+                }
+                NodeKind::AtRule { name, .. } if name == "layer" || name == "utility" => {}
+                NodeKind::AtRule {
+                    block: Some(block), ..
+                } => self.visit(block)?,
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn fill_directives(&mut self) {
+        let mut variables = theme_variables(&self.config, self.source);
+        variables.extend(std::mem::take(&mut self.variables));
+        // The variables go after the base layer, or before the first
+        // directive when there is none.
+        let has_base = self
+            .directives
+            .iter()
+            .any(|(_, layers)| layers.contains(&Layer::Base));
+        for (index, (span, layers)) in std::mem::take(&mut self.directives).into_iter().enumerate()
+        {
+            let mut css = Vec::new();
+            if !has_base && index == 0 && !variables.is_empty() {
+                css.push(variables.to_string());
+            }
+            for layer in layers {
+                css.push(write_layer(&self.config, self.source, layer));
+                if layer == Layer::Base && !variables.is_empty() {
+                    css.push(variables.to_string());
+                }
+                css.extend(self.layers.get(&layer).cloned().unwrap_or_default());
+            }
+            css.retain(|css| !css.is_empty());
+            self.edits.push((span, css.join("\n")));
+        }
+    }
+
+    // Inline the declarations of every `@apply` in `block`. Rules that need
+    // their own selector or at-rule, like `hover:` variants, are added after
+    // the rule with the class swapped for `selector`, and replace it when
+    // nothing else is left in it.
+    fn expand_apply(&mut self, rule: &Node, selector: &str, block: &[Node]) -> Result<(), Error> {
+        let mut extra: Vec<StyleRule> = Vec::new();
+        let mut edits = Vec::new();
+        let mut emptied = 0;
+        for node in block {
+            let classes = match &node.kind {
+                NodeKind::AtRule { name, params, .. } if name == "apply" => params,
+                _ => continue,
+            };
+
+            let mut declarations = Vec::new();
+            for class in classes.split_whitespace() {
+                let base = format!(".{}", class.escape_class_name());
+                for mut css in self.apply_rules(node, class)? {
+                    if css.selector == base && css.at_rules.is_empty() {
+                        declarations.extend(css.declarations);
+                        continue;
+                    }
+                    css.selector = selector
+                        .split(',')
+                        .map(|part| css.selector.replace(&base, part.trim()))
+                        .collect::<Vec<_>>()
+                        .join(", ");
+                    extra.push(css);
+                }
+            }
+
+            // Nothing left to inline: drop the line of the `@apply` too.
+            if declarations.is_empty() {
+                edits.push((removal_span(self.input, &node.span), String::new()));
+                emptied += 1;
+                continue;
+            }
+            let separator = format!("\n{}", indentation(self.input, node.span.start));
+            let declarations: Vec<String> = declarations
+                .iter()
+                .map(|(property, value)| format!("{}: {};", property, value))
+                .collect();
+            edits.push((node.span.clone(), declarations.join(&separator)));
+        }
+
+        let indent = indentation(self.input, rule.span.start);
+        let extra: Vec<String> = extra
+            .iter()
+            .map(|css| css.to_string().replace('\n', &format!("\n{}", indent)))
+            .collect();
+        if !extra.is_empty() && emptied == block.len() {
+            let css = extra.join(&format!("\n{}", indent));
+            self.edits.push((rule.span.clone(), css));
+            return Ok(());
+        }
+        self.edits.extend(edits);
+        if !extra.is_empty() {
+            let css: String = extra
+                .iter()
+                .map(|css| format!("\n{}{}", indent, css))
+                .collect();
+            self.edits.push((rule.span.end..rule.span.end, css));
+        }
+        Ok(())
+    }
+}
+
+/// Replace the Tailwind directives and functions of `input` with the
+/// generated CSS, leaving everything else untouched. `file` is only used in errors.
+pub fn process(
+    input: &str,
+    file: &str,
+    config: &Config,
+    source: &HashSet<String>,
+) -> Result<String, Error> {
+    let input = &functions::resolve(input, file, config)?;
+    let mut stylesheet = Stylesheet {
+        input,
+        file,
+        config: config.clone(),
+        source,
+        layers: HashMap::new(),
+        directives: Vec::new(),
+        variables: Variables::default(),
+        edits: Vec::new(),
+    };
+    let nodes = parser::parse(input);
+    stylesheet.register(&nodes)?;
+    stylesheet.visit(&nodes)?;
+    stylesheet.fill_directives();
+
+    let mut edits = stylesheet.edits;
+    edits.sort_by_key(|(span, _)| span.start);
+    let mut output = String::with_capacity(input.len());
+    let mut pos = 0;
+    for (span, css) in edits {
+        output.push_str(&input[pos..span.start]);
+        output.push_str(&css);
+        pos = span.end;
+    }
+    output.push_str(&input[pos..]);
+    Ok(output)
+}
+
+pub fn execute(
+    input: &str,
+    source: &HashSet<String>,
+    output: &str,
+    config: &Config,
+) -> Result<(), Error> {
+    let css = std::fs::read_to_string(input).map_err(|e| Error::Io(input.to_string(), e))?;
+    let css = process(&css, input, config, source)?;
+    std::fs::write(Path::new(output), css).map_err(|e| Error::Io(output.to_string(), e))
+}
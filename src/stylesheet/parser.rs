@@ -0,0 +1,206 @@
+use std::ops::Range;
+
+// A statement of the input stylesheet. Spans index into the source so that
+// anything not rewritten is copied through byte for byte.
+#[derive(Debug, Clone)]
+pub struct Node {
+    pub kind: NodeKind,
+    pub span: Range<usize>,
+    pub line: usize,
+}
+
+#[derive(Debug, Clone)]
+pub enum NodeKind {
+    AtRule {
+        name: String,
+        params: String,
+        block: Option<Vec<Node>>,
+    },
+    Rule {
+        selector: String,
+        block: Vec<Node>,
+    },
+    Declaration {
+        property: String,
+        value: String,
+    },
+    Comment,
+}
+
+struct Parser<'a> {
+    source: &'a str,
+    bytes: &'a [u8],
+    pos: usize,
+    line_starts: Vec<usize>,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.pos).copied()
+    }
+
+    fn starts_with(&self, pattern: &str) -> bool {
+        self.source[self.pos..].starts_with(pattern)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while matches!(self.peek(), Some(c) if c.is_ascii_whitespace()) {
+            self.pos += 1;
+        }
+    }
+
+    fn skip_comment(&mut self) {
+        self.pos = match self.source[self.pos + 2..].find("*/") {
+            Some(end) => self.pos + 2 + end + 2,
+            None => self.bytes.len(),
+        };
+    }
+
+    fn skip_string(&mut self, quote: u8) {
+        self.pos += 1;
+        while let Some(c) = self.peek() {
+            self.pos += 1;
+            if c == b'\\' {
+                self.pos += 1;
+            } else if c == quote {
+                break;
+            }
+        }
+        self.pos = self.pos.min(self.bytes.len());
+    }
+
+    // Advance to the `;`, `{` or `}` that ends the current prelude.
+    fn skip_prelude(&mut self) {
+        let mut depth = 0;
+        while let Some(c) = self.peek() {
+            match c {
+                b'/' if self.starts_with("/*") => {
+                    self.skip_comment();
+                    continue;
+                }
+                b'"' | b'\'' => {
+                    self.skip_string(c);
+                    continue;
+                }
+                b'(' | b'[' => depth += 1,
+                b')' | b']' => depth -= 1,
+                b';' | b'{' | b'}' if depth <= 0 => return,
+                _ => {}
+            }
+            self.pos += 1;
+        }
+    }
+
+    fn parse_block(&mut self) -> Vec<Node> {
+        let mut nodes = Vec::new();
+        loop {
+            self.skip_whitespace();
+            let start = self.pos;
+            match self.peek() {
+                None | Some(b'}') => return nodes,
+                Some(b'/') if self.starts_with("/*") => {
+                    self.skip_comment();
+                    nodes.push(self.node(NodeKind::Comment, start));
+                    continue;
+                }
+                _ => {}
+            }
+
+            self.skip_prelude();
+            let prelude = self.source[start..self.pos].trim().to_string();
+            let kind = match self.peek() {
+                Some(b'{') => {
+                    self.pos += 1;
+                    let block = self.parse_block();
+                    if self.peek() == Some(b'}') {
+                        self.pos += 1;
+                    }
+                    match prelude.strip_prefix('@') {
+                        Some(at_rule) => {
+                            let (name, params) = split_at_rule(at_rule);
+                            NodeKind::AtRule {
+                                name,
+                                params,
+                                block: Some(block),
+                            }
+                        }
+                        None => NodeKind::Rule {
+                            selector: prelude,
+                            block,
+                        },
+                    }
+                }
+                end => {
+                    if end == Some(b';') {
+                        self.pos += 1;
+                    }
+                    match prelude.strip_prefix('@') {
+                        Some(at_rule) => {
+                            let (name, params) = split_at_rule(at_rule);
+                            NodeKind::AtRule {
+                                name,
+                                params,
+                                block: None,
+                            }
+                        }
+                        None => {
+                            let (property, value) =
+                                prelude.split_once(':').unwrap_or((&prelude, ""));
+                            NodeKind::Declaration {
+                                property: property.trim().to_string(),
+                                value: value.trim().to_string(),
+                            }
+                        }
+                    }
+                }
+            };
+            nodes.push(self.node(kind, start));
+        }
+    }
+
+    fn node(&self, kind: NodeKind, start: usize) -> Node {
+        Node {
+            kind,
+            span: start..self.pos,
+            line: line_of(&self.line_starts, start),
+        }
+    }
+}
+
+fn split_at_rule(at_rule: &str) -> (String, String) {
+    let end = at_rule
+        .find(|c: char| c.is_whitespace() || c == '(' || c == '"' || c == '\'')
+        .unwrap_or(at_rule.len());
+    (
+        at_rule[..end].to_string(),
+        at_rule[end..].trim().to_string(),
+    )
+}
+
+/// Byte offset at which each line of `source` starts.
+pub fn line_starts(source: &str) -> Vec<usize> {
+    let breaks = source.match_indices('\n').map(|(i, _)| i + 1);
+    std::iter::once(0).chain(breaks).collect()
+}
+
+/// 1-based line number of a byte offset, given the `line_starts` of its
+/// source.
+pub fn line_of(line_starts: &[usize], offset: usize) -> usize {
+    line_starts.partition_point(|start| *start <= offset)
+}
+
+pub fn parse(source: &str) -> Vec<Node> {
+    let mut parser = Parser {
+        source,
+        bytes: source.as_bytes(),
+        pos: 0,
+        line_starts: line_starts(source),
+    };
+    let mut nodes = parser.parse_block();
+    // A stray `}` at the top level: keep going after it.
+    while parser.peek() == Some(b'}') {
+        parser.pos += 1;
+        nodes.extend(parser.parse_block());
+    }
+    nodes
+}
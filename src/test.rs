@@ -1,8 +1,11 @@
+use std::cell::RefCell;
 use std::collections::HashSet;
+use std::rc::Rc;
 
 use crate::{
     config,
-    generator::{write_css, Buffer},
+    generator::{select_classes, write_css, Buffer},
+    stylesheet,
 };
 
 #[macro_export]
@@ -16,6 +19,20 @@ macro_rules! set {
     };
 }
 
+// The CSS written for `source`, one entry per write.
+fn generate_css(config: &config::Config, source: &HashSet<String>) -> Vec<String> {
+    struct Collect(Rc<RefCell<Vec<String>>>);
+    impl Buffer for Collect {
+        fn write(&mut self, data: &str) {
+            self.0.borrow_mut().push(data.to_string());
+        }
+    }
+    let output = Rc::new(RefCell::new(Vec::new()));
+    write_css(Box::new(Collect(output.clone())), config, source);
+    let output = output.borrow().clone();
+    output
+}
+
 #[test]
 fn test_aspect_ratio() {
     let config_set = config::parse(include_str!("default-config.json")).unwrap();
@@ -378,7 +395,7 @@ fn test_flex_grow() {
         ("grow-0", ".flex-auto {\n\tflex: 1 1 auto;\n}"),
     ];
 
-    struct Buf<>(String);
+    struct Buf(String);
     impl Buffer for Buf {
         fn write(&mut self, data: &str) {
             assert_eq!(data, self.0);
@@ -459,3 +476,1586 @@ fn test_font_size() {
         write_css(Box::new(Buf(expected.into())), &config_set, &set![class]);
     }
 }
+
+#[test]
+fn test_font_weight() {
+    let config_set = config::parse(include_str!("default-config.json")).unwrap();
+    let test_case = vec![
+        ("font-thin", ".font-thin {\n\tfont-weight: 100;\n}"),
+        ("font-normal", ".font-normal {\n\tfont-weight: 400;\n}"),
+        ("font-bold", ".font-bold {\n\tfont-weight: 700;\n}"),
+    ];
+
+    for (class, expected) in test_case {
+        assert_eq!(generate_css(&config_set, &set![class]), [expected]);
+    }
+}
+
+#[test]
+fn test_filter() {
+    let config_set = config::parse(include_str!("default-config.json")).unwrap();
+    let filter = "var(--tw-blur,) var(--tw-brightness,) var(--tw-contrast,) var(--tw-grayscale,) var(--tw-hue-rotate,) var(--tw-invert,) var(--tw-saturate,) var(--tw-sepia,) var(--tw-drop-shadow,)";
+    let backdrop = "var(--tw-backdrop-blur,) var(--tw-backdrop-brightness,) var(--tw-backdrop-contrast,) var(--tw-backdrop-grayscale,) var(--tw-backdrop-hue-rotate,) var(--tw-backdrop-invert,) var(--tw-backdrop-opacity,) var(--tw-backdrop-saturate,) var(--tw-backdrop-sepia,)";
+    let test_case = vec![
+        (
+            "blur-sm",
+            format!(".blur-sm {{\n\t--tw-blur: blur(4px);\n\tfilter: {};\n}}", filter),
+        ),
+        (
+            "blur",
+            format!(".blur {{\n\t--tw-blur: blur(8px);\n\tfilter: {};\n}}", filter),
+        ),
+        (
+            "brightness-50",
+            format!(".brightness-50 {{\n\t--tw-brightness: brightness(.5);\n\tfilter: {};\n}}", filter),
+        ),
+        (
+            "contrast-125",
+            format!(".contrast-125 {{\n\t--tw-contrast: contrast(1.25);\n\tfilter: {};\n}}", filter),
+        ),
+        (
+            "grayscale",
+            format!(".grayscale {{\n\t--tw-grayscale: grayscale(100%);\n\tfilter: {};\n}}", filter),
+        ),
+        (
+            "-hue-rotate-15",
+            format!(".-hue-rotate-15 {{\n\t--tw-hue-rotate: hue-rotate(-15deg);\n\tfilter: {};\n}}", filter),
+        ),
+        (
+            "drop-shadow-lg",
+            format!(".drop-shadow-lg {{\n\t--tw-drop-shadow: drop-shadow(0 10px 8px rgb(0 0 0 / 0.04)) drop-shadow(0 4px 3px rgb(0 0 0 / 0.1));\n\tfilter: {};\n}}", filter),
+        ),
+        ("filter-none", ".filter-none {\n\tfilter: none;\n}".into()),
+        (
+            "backdrop-blur-md",
+            format!(".backdrop-blur-md {{\n\t--tw-backdrop-blur: blur(12px);\n\tbackdrop-filter: {};\n}}", backdrop),
+        ),
+        (
+            "backdrop-saturate-150",
+            format!(".backdrop-saturate-150 {{\n\t--tw-backdrop-saturate: saturate(1.5);\n\tbackdrop-filter: {};\n}}", backdrop),
+        ),
+        (
+            "backdrop-filter-none",
+            ".backdrop-filter-none {\n\tbackdrop-filter: none;\n}".into(),
+        ),
+    ];
+
+    for (class, expected) in test_case {
+        assert_eq!(generate_css(&config_set, &set![class]), [expected]);
+    }
+}
+
+#[test]
+fn test_background() {
+    let config_set = config::parse(include_str!("default-config.json")).unwrap();
+    let test_case = vec![
+        ("bg-black", ".bg-black {\n\tbackground-color: #000;\n}"),
+        (
+            "bg-blue-500",
+            ".bg-blue-500 {\n\tbackground-color: #3b82f6;\n}",
+        ),
+        (
+            "bg-blue-500/50",
+            ".bg-blue-500\\/50 {\n\tbackground-color: rgb(59 130 246 / 0.5);\n}",
+        ),
+        (
+            "bg-gradient-to-r",
+            ".bg-gradient-to-r {\n\tbackground-image: linear-gradient(to right, var(--tw-gradient-stops));\n}",
+        ),
+        ("bg-none", ".bg-none {\n\tbackground-image: none;\n}"),
+        ("bg-cover", ".bg-cover {\n\tbackground-size: cover;\n}"),
+        ("bg-center", ".bg-center {\n\tbackground-position: center;\n}"),
+        (
+            "bg-no-repeat",
+            ".bg-no-repeat {\n\tbackground-repeat: no-repeat;\n}",
+        ),
+        ("bg-fixed", ".bg-fixed {\n\tbackground-attachment: fixed;\n}"),
+        ("bg-clip-text", ".bg-clip-text {\n\tbackground-clip: text;\n}"),
+        (
+            "bg-[url(/img/hero.png)]",
+            ".bg-\\[url\\(\\/img\\/hero\\.png\\)\\] {\n\tbackground-image: url(/img/hero.png);\n}",
+        ),
+        (
+            "bg-[url(/img/a_b.png)]",
+            ".bg-\\[url\\(\\/img\\/a_b\\.png\\)\\] {\n\tbackground-image: url(/img/a_b.png);\n}",
+        ),
+        (
+            "bg-[linear-gradient(to_right,red,url(a_b.png))]",
+            ".bg-\\[linear-gradient\\(to_right\\,red\\,url\\(a_b\\.png\\)\\)\\] {\n\tbackground-image: linear-gradient(to right,red,url(a_b.png));\n}",
+        ),
+        (
+            "bg-[#1da1f2]",
+            ".bg-\\[\\#1da1f2\\] {\n\tbackground-color: #1da1f2;\n}",
+        ),
+    ];
+
+    for (class, expected) in test_case {
+        assert_eq!(generate_css(&config_set, &set![class]), [expected]);
+    }
+}
+
+#[test]
+fn test_gradient_color_stops() {
+    let config_set = config::parse(include_str!("default-config.json")).unwrap();
+    let test_case = vec![
+        (
+            "from-red-500",
+            ".from-red-500 {\n\t--tw-gradient-from: #ef4444 var(--tw-gradient-from-position,);\n\t--tw-gradient-to: rgb(239 68 68 / 0) var(--tw-gradient-to-position,);\n\t--tw-gradient-stops: var(--tw-gradient-from), var(--tw-gradient-to);\n}",
+        ),
+        (
+            "via-white/50",
+            ".via-white\\/50 {\n\t--tw-gradient-to: rgb(255 255 255 / 0) var(--tw-gradient-to-position,);\n\t--tw-gradient-stops: var(--tw-gradient-from), rgb(255 255 255 / 0.5) var(--tw-gradient-via-position,), var(--tw-gradient-to);\n}",
+        ),
+        (
+            "to-blue-500",
+            ".to-blue-500 {\n\t--tw-gradient-to: #3b82f6 var(--tw-gradient-to-position,);\n}",
+        ),
+        (
+            "from-10%",
+            ".from-10\\% {\n\t--tw-gradient-from-position: 10%;\n}",
+        ),
+        (
+            "to-[85%]",
+            ".to-\\[85\\%\\] {\n\t--tw-gradient-to-position: 85%;\n}",
+        ),
+    ];
+
+    for (class, expected) in test_case {
+        assert_eq!(generate_css(&config_set, &set![class]), [expected]);
+    }
+}
+
+#[test]
+fn test_interactivity() {
+    let config_set = config::parse(include_str!("default-config.json")).unwrap();
+    let test_case = vec![
+        ("cursor-pointer", ".cursor-pointer {\n\tcursor: pointer;\n}"),
+        (
+            "cursor-not-allowed",
+            ".cursor-not-allowed {\n\tcursor: not-allowed;\n}",
+        ),
+        (
+            "pointer-events-none",
+            ".pointer-events-none {\n\tpointer-events: none;\n}",
+        ),
+        ("select-none", ".select-none {\n\tuser-select: none;\n}"),
+        ("resize", ".resize {\n\tresize: both;\n}"),
+        ("resize-y", ".resize-y {\n\tresize: vertical;\n}"),
+        (
+            "scroll-smooth",
+            ".scroll-smooth {\n\tscroll-behavior: smooth;\n}",
+        ),
+        (
+            "snap-x",
+            ".snap-x {\n\tscroll-snap-type: x var(--tw-scroll-snap-strictness, proximity);\n}",
+        ),
+        (
+            "snap-mandatory",
+            ".snap-mandatory {\n\t--tw-scroll-snap-strictness: mandatory;\n}",
+        ),
+        (
+            "snap-center",
+            ".snap-center {\n\tscroll-snap-align: center;\n}",
+        ),
+        (
+            "snap-always",
+            ".snap-always {\n\tscroll-snap-stop: always;\n}",
+        ),
+        (
+            "touch-manipulation",
+            ".touch-manipulation {\n\ttouch-action: manipulation;\n}",
+        ),
+        (
+            "appearance-none",
+            ".appearance-none {\n\tappearance: none;\n}",
+        ),
+        (
+            "will-change-scroll",
+            ".will-change-scroll {\n\twill-change: scroll-position;\n}",
+        ),
+        ("accent-auto", ".accent-auto {\n\taccent-color: auto;\n}"),
+        (
+            "accent-pink-500",
+            ".accent-pink-500 {\n\taccent-color: #ec4899;\n}",
+        ),
+        (
+            "caret-current",
+            ".caret-current {\n\tcaret-color: currentColor;\n}",
+        ),
+    ];
+
+    for (class, expected) in test_case {
+        assert_eq!(generate_css(&config_set, &set![class]), [expected]);
+    }
+}
+
+#[test]
+fn test_fill_and_stroke() {
+    let config_set = config::parse(include_str!("default-config.json")).unwrap();
+    let test_case = vec![
+        ("fill-current", ".fill-current {\n\tfill: currentColor;\n}"),
+        ("fill-none", ".fill-none {\n\tfill: none;\n}"),
+        ("fill-red-500", ".fill-red-500 {\n\tfill: #ef4444;\n}"),
+        (
+            "fill-[#243c5a]",
+            ".fill-\\[\\#243c5a\\] {\n\tfill: #243c5a;\n}",
+        ),
+        ("stroke-none", ".stroke-none {\n\tstroke: none;\n}"),
+        ("stroke-sky-500", ".stroke-sky-500 {\n\tstroke: #0ea5e9;\n}"),
+        (
+            "stroke-black/25",
+            ".stroke-black\\/25 {\n\tstroke: rgb(0 0 0 / 0.25);\n}",
+        ),
+        ("stroke-2", ".stroke-2 {\n\tstroke-width: 2;\n}"),
+        (
+            "stroke-[3px]",
+            ".stroke-\\[3px\\] {\n\tstroke-width: 3px;\n}",
+        ),
+    ];
+
+    for (class, expected) in test_case {
+        assert_eq!(generate_css(&config_set, &set![class]), [expected]);
+    }
+}
+
+#[test]
+fn test_opacity_and_blend() {
+    let config_set = config::parse(include_str!("default-config.json")).unwrap();
+    let test_case = vec![
+        ("opacity-0", ".opacity-0 {\n\topacity: 0;\n}"),
+        ("opacity-5", ".opacity-5 {\n\topacity: 0.05;\n}"),
+        ("opacity-50", ".opacity-50 {\n\topacity: 0.5;\n}"),
+        ("opacity-100", ".opacity-100 {\n\topacity: 1;\n}"),
+        (
+            "opacity-[.67]",
+            ".opacity-\\[\\.67\\] {\n\topacity: .67;\n}",
+        ),
+        (
+            "bg-red-500/[.67]",
+            ".bg-red-500\\/\\[\\.67\\] {\n\tbackground-color: rgb(239 68 68 / .67);\n}",
+        ),
+        (
+            "mix-blend-multiply",
+            ".mix-blend-multiply {\n\tmix-blend-mode: multiply;\n}",
+        ),
+        (
+            "mix-blend-plus-lighter",
+            ".mix-blend-plus-lighter {\n\tmix-blend-mode: plus-lighter;\n}",
+        ),
+        (
+            "bg-blend-screen",
+            ".bg-blend-screen {\n\tbackground-blend-mode: screen;\n}",
+        ),
+        (
+            "backdrop-opacity-75",
+            ".backdrop-opacity-75 {\n\t--tw-backdrop-opacity: opacity(0.75);\n\tbackdrop-filter: var(--tw-backdrop-blur,) var(--tw-backdrop-brightness,) var(--tw-backdrop-contrast,) var(--tw-backdrop-grayscale,) var(--tw-backdrop-hue-rotate,) var(--tw-backdrop-invert,) var(--tw-backdrop-opacity,) var(--tw-backdrop-saturate,) var(--tw-backdrop-sepia,);\n}",
+        ),
+    ];
+
+    for (class, expected) in test_case {
+        assert_eq!(generate_css(&config_set, &set![class]), [expected]);
+    }
+}
+
+#[test]
+fn test_tables_lists_and_outline() {
+    let config_set = config::parse(include_str!("default-config.json")).unwrap();
+    let test_case = vec![
+        (
+            "border-collapse",
+            ".border-collapse {\n\tborder-collapse: collapse;\n}",
+        ),
+        (
+            "border-spacing-2",
+            ".border-spacing-2 {\n\t--tw-border-spacing-x: 0.5rem;\n\t--tw-border-spacing-y: 0.5rem;\n\tborder-spacing: var(--tw-border-spacing-x, 0) var(--tw-border-spacing-y, 0);\n}",
+        ),
+        (
+            "border-spacing-x-px",
+            ".border-spacing-x-px {\n\t--tw-border-spacing-x: 1px;\n\tborder-spacing: var(--tw-border-spacing-x, 0) var(--tw-border-spacing-y, 0);\n}",
+        ),
+        ("table-auto", ".table-auto {\n\ttable-layout: auto;\n}"),
+        ("table-fixed", ".table-fixed {\n\ttable-layout: fixed;\n}"),
+        ("caption-bottom", ".caption-bottom {\n\tcaption-side: bottom;\n}"),
+        ("list-disc", ".list-disc {\n\tlist-style-type: disc;\n}"),
+        (
+            "list-inside",
+            ".list-inside {\n\tlist-style-position: inside;\n}",
+        ),
+        (
+            "list-image-none",
+            ".list-image-none {\n\tlist-style-image: none;\n}",
+        ),
+        (
+            "list-image-[url(check.svg)]",
+            ".list-image-\\[url\\(check\\.svg\\)\\] {\n\tlist-style-image: url(check.svg);\n}",
+        ),
+        ("outline", ".outline {\n\toutline-style: solid;\n}"),
+        ("outline-none", ".outline-none {\n\toutline-style: none;\n}"),
+        (
+            "outline-dashed",
+            ".outline-dashed {\n\toutline-style: dashed;\n}",
+        ),
+        ("outline-2", ".outline-2 {\n\toutline-width: 2px;\n}"),
+        (
+            "outline-offset-2",
+            ".outline-offset-2 {\n\toutline-offset: 2px;\n}",
+        ),
+        (
+            "outline-blue-500",
+            ".outline-blue-500 {\n\toutline-color: #3b82f6;\n}",
+        ),
+    ];
+
+    for (class, expected) in test_case {
+        assert_eq!(generate_css(&config_set, &set![class]), [expected]);
+    }
+}
+
+#[test]
+fn test_accessibility() {
+    let config_set = config::parse(include_str!("default-config.json")).unwrap();
+    let test_case = vec![
+        (
+            "sr-only",
+            ".sr-only {\n\tposition: absolute;\n\twidth: 1px;\n\theight: 1px;\n\tpadding: 0;\n\tmargin: -1px;\n\toverflow: hidden;\n\tclip: rect(0, 0, 0, 0);\n\twhite-space: nowrap;\n\tborder-width: 0;\n}",
+        ),
+        (
+            "not-sr-only",
+            ".not-sr-only {\n\tposition: static;\n\twidth: auto;\n\theight: auto;\n\tpadding: 0;\n\tmargin: 0;\n\toverflow: visible;\n\tclip: auto;\n\twhite-space: normal;\n}",
+        ),
+        (
+            "forced-color-adjust-none",
+            ".forced-color-adjust-none {\n\tforced-color-adjust: none;\n}",
+        ),
+        (
+            "forced-colors:outline",
+            "@media (forced-colors: active) {\n\t.forced-colors\\:outline {\n\t\toutline-style: solid;\n\t}\n}",
+        ),
+        (
+            "motion-reduce:blur-none",
+            "@media (prefers-reduced-motion: reduce) {\n\t.motion-reduce\\:blur-none {\n\t\t--tw-blur: blur(0);\n\t\tfilter: var(--tw-blur,) var(--tw-brightness,) var(--tw-contrast,) var(--tw-grayscale,) var(--tw-hue-rotate,) var(--tw-invert,) var(--tw-saturate,) var(--tw-sepia,) var(--tw-drop-shadow,);\n\t}\n}",
+        ),
+        (
+            "motion-safe:scroll-smooth",
+            "@media (prefers-reduced-motion: no-preference) {\n\t.motion-safe\\:scroll-smooth {\n\t\tscroll-behavior: smooth;\n\t}\n}",
+        ),
+        (
+            "md:hover:bg-red-500",
+            "@media (min-width: 768px) {\n\t.md\\:hover\\:bg-red-500:hover {\n\t\tbackground-color: #ef4444;\n\t}\n}",
+        ),
+        (
+            "focus:sr-only",
+            ".focus\\:sr-only:focus {\n\tposition: absolute;\n\twidth: 1px;\n\theight: 1px;\n\tpadding: 0;\n\tmargin: -1px;\n\toverflow: hidden;\n\tclip: rect(0, 0, 0, 0);\n\twhite-space: nowrap;\n\tborder-width: 0;\n}",
+        ),
+    ];
+
+    for (class, expected) in test_case {
+        assert_eq!(generate_css(&config_set, &set![class]), [expected]);
+    }
+}
+
+#[test]
+fn test_container() {
+    let mut config_set = config::parse(include_str!("default-config.json")).unwrap();
+
+    struct Buf(Rc<RefCell<Vec<String>>>);
+    impl Buffer for Buf {
+        fn write(&mut self, data: &str) {
+            self.0.borrow_mut().push(data.into())
+        }
+    }
+
+    let output = Rc::new(RefCell::new(Vec::new()));
+    write_css(
+        Box::new(Buf(output.clone())),
+        &config_set,
+        &set!["container"],
+    );
+    assert_eq!(
+        *output.borrow(),
+        vec![
+            ".container {\n\twidth: 100%;\n}",
+            "@media (min-width: 640px) {\n\t.container {\n\t\tmax-width: 640px;\n\t}\n}",
+            "@media (min-width: 768px) {\n\t.container {\n\t\tmax-width: 768px;\n\t}\n}",
+            "@media (min-width: 1024px) {\n\t.container {\n\t\tmax-width: 1024px;\n\t}\n}",
+            "@media (min-width: 1280px) {\n\t.container {\n\t\tmax-width: 1280px;\n\t}\n}",
+            "@media (min-width: 1536px) {\n\t.container {\n\t\tmax-width: 1536px;\n\t}\n}",
+        ]
+    );
+
+    config_set.container.center = true;
+    config_set.container.padding = [("DEFAULT", "1rem"), ("lg", "4rem")]
+        .iter()
+        .map(|(screen, size)| (screen.to_string(), size.to_string()))
+        .collect();
+    let output = Rc::new(RefCell::new(Vec::new()));
+    write_css(
+        Box::new(Buf(output.clone())),
+        &config_set,
+        &set!["container"],
+    );
+    let output = output.borrow();
+    assert_eq!(
+        output[0],
+        ".container {\n\twidth: 100%;\n\tmargin-right: auto;\n\tmargin-left: auto;\n\tpadding-right: 1rem;\n\tpadding-left: 1rem;\n}"
+    );
+    assert_eq!(
+        output[1],
+        "@media (min-width: 640px) {\n\t.container {\n\t\tmax-width: 640px;\n\t}\n}"
+    );
+    assert_eq!(
+        output[3],
+        "@media (min-width: 1024px) {\n\t.container {\n\t\tmax-width: 1024px;\n\t\tpadding-right: 4rem;\n\t\tpadding-left: 4rem;\n\t}\n}"
+    );
+}
+
+#[test]
+fn test_preflight_switch() {
+    let config_set = config::parse(include_str!("default-config.json")).unwrap();
+    assert!(config_set.preflight);
+
+    let source = include_str!("default-config.json").replace(
+        r#""corePlugins": {"preflight":true}"#,
+        r#""corePlugins": {"preflight":false}"#,
+    );
+    let config_set = config::parse(&source).unwrap();
+    assert!(!config_set.preflight);
+}
+
+#[test]
+fn test_preflight_output() {
+    use crate::generator::{execute, write_layer, Layer};
+    use clap::Parser;
+
+    let path = std::env::temp_dir().join("tailwindcss-rs-test-preflight.css");
+    let output = path.to_str().unwrap();
+    let source = set!["flex"];
+    let utilities = ".flex {\n\tdisplay: flex;\n}\n";
+
+    let config_set = config::parse(include_str!("default-config.json")).unwrap();
+    execute(&source, output, &config_set);
+    let css = std::fs::read_to_string(&path).unwrap();
+    assert!(css.starts_with("/*\n1. Prevent padding"));
+    assert!(css.ends_with(utilities));
+    assert!(css.find("Prevent padding") < css.find(".flex {"));
+
+    let base = write_layer(&config_set, &source, Layer::Base);
+    assert!(base.starts_with("/*\n1. Prevent padding"));
+    assert!(!base.contains(".flex"));
+    let css = stylesheet::process(
+        "@tailwind base;\n@tailwind utilities;\n",
+        "input.css",
+        &config_set,
+        &source,
+    )
+    .unwrap();
+    assert!(css.starts_with("/*\n1. Prevent padding"));
+    assert!(css.ends_with(utilities));
+
+    // `--no-preflight`, applied the way `watch` does.
+    let args =
+        crate::Application::parse_from(["tailwindcss-rs", "-i", "a", "-o", "b", "--no-preflight"]);
+    let mut config_set = config::parse(include_str!("default-config.json")).unwrap();
+    config_set.preflight = config_set.preflight && !args.no_preflight;
+    execute(&source, output, &config_set);
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), utilities);
+    assert_eq!(write_layer(&config_set, &source, Layer::Base), "");
+
+    let config_set = config::parse(&include_str!("default-config.json").replace(
+        r#""corePlugins": {"preflight":true}"#,
+        r#""corePlugins": {"preflight":false}"#,
+    ))
+    .unwrap();
+    execute(&source, output, &config_set);
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), utilities);
+    assert_eq!(write_layer(&config_set, &source, Layer::Base), "");
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_output_order() {
+    let config_set = config::parse(include_str!("default-config.json")).unwrap();
+
+    struct Buf(Rc<RefCell<Vec<String>>>);
+    impl Buffer for Buf {
+        fn write(&mut self, data: &str) {
+            let selector = data.split(" {").next().unwrap().to_string();
+            self.0.borrow_mut().push(selector)
+        }
+    }
+
+    let source = set![
+        "md:hover:bg-red-500",
+        "hover:flex",
+        "md:m-2",
+        "to-blue-500",
+        "from-white",
+        "mt-4",
+        "mx-1",
+        "m-2",
+        "overflow-x-auto",
+        "overflow-hidden",
+        "flex"
+    ];
+    let mut first = None;
+    for _ in 0..5 {
+        let output = Rc::new(RefCell::new(Vec::new()));
+        write_css(Box::new(Buf(output.clone())), &config_set, &source);
+        let output = output.borrow().clone();
+        assert_eq!(
+            output,
+            vec![
+                ".m-2",
+                ".mx-1",
+                ".mt-4",
+                ".flex",
+                ".overflow-hidden",
+                ".overflow-x-auto",
+                ".from-white",
+                ".to-blue-500",
+                ".hover\\:flex:hover",
+                "@media (min-width: 768px)",
+                "@media (min-width: 768px)",
+            ]
+        );
+        if let Some(first) = &first {
+            assert_eq!(first, &output);
+        }
+        first = Some(output);
+    }
+}
+
+#[test]
+fn test_stylesheet_directives() {
+    let mut config_set = config::parse(include_str!("default-config.json")).unwrap();
+    config_set.preflight = false;
+    let source = set!["container", "flex", "m-2"];
+
+    let input = "@tailwind base;\n@tailwind components;\n\n/* keep me; {} */\n.btn {\n  color: red;\n}\n\n@tailwind utilities;\n";
+    let output = stylesheet::process(input, "input.css", &config_set, &source).unwrap();
+    assert!(output.starts_with("\n.container {\n\twidth: 100%;\n}"));
+    assert!(output.contains("/* keep me; {} */\n.btn {\n  color: red;\n}\n\n.m-2 {\n\tmargin: 0.5rem;\n}\n.flex {\n\tdisplay: flex;\n}\n"));
+
+    let input = "@import \"tailwindcss\";\n@media print {\n  .a { b: c }\n}\n";
+    let output = stylesheet::process(input, "input.css", &config_set, &set!["flex"]).unwrap();
+    assert_eq!(
+        output,
+        ".flex {\n\tdisplay: flex;\n}\n@media print {\n  .a { b: c }\n}\n"
+    );
+
+    config_set.preflight = true;
+    let output =
+        stylesheet::process("@tailwind base;", "input.css", &config_set, &HashSet::new()).unwrap();
+    assert!(output.starts_with("/*\n1. Prevent padding"));
+}
+
+#[test]
+fn test_apply() {
+    let config_set = config::parse(include_str!("default-config.json")).unwrap();
+    let source = HashSet::new();
+
+    let input = ".btn, .button {\n  @apply px-4 flex hover:bg-red-500;\n  color: red;\n}\n";
+    let output = stylesheet::process(input, "input.css", &config_set, &source).unwrap();
+    assert_eq!(
+        output,
+        ".btn, .button {\n  padding-left: 1rem;\n  padding-right: 1rem;\n  display: flex;\n  color: red;\n}\n.btn:hover, .button:hover {\n\tbackground-color: #ef4444;\n}\n"
+    );
+
+    let input = ".card {\n  @apply md:m-2;\n}";
+    let output = stylesheet::process(input, "input.css", &config_set, &source).unwrap();
+    assert_eq!(
+        output,
+        "@media (min-width: 768px) {\n\t.card {\n\t\tmargin: 0.5rem;\n\t}\n}"
+    );
+
+    let input = "@media print {\n  .btn { @apply hover:bg-red-500; }\n}\n";
+    let output = stylesheet::process(input, "input.css", &config_set, &source).unwrap();
+    assert_eq!(
+        output,
+        "@media print {\n  .btn:hover {\n  \tbackground-color: #ef4444;\n  }\n}\n"
+    );
+
+    let input = "a {}\n.btn {\n  @apply flex not-a-class;\n}";
+    let error = stylesheet::process(input, "input.css", &config_set, &source).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "input.css:3: cannot `@apply` unknown class `not-a-class`"
+    );
+}
+
+#[test]
+fn test_custom_layers() {
+    let mut config_set = config::parse(include_str!("default-config.json")).unwrap();
+    config_set.preflight = false;
+
+    let input = "@tailwind base;
+@tailwind components;
+@tailwind utilities;
+
+@layer base {
+  h1 { font-size: 2rem; }
+}
+
+@layer components {
+  .card {
+    padding: 1rem;
+    @apply flex hover:bg-red-500;
+  }
+  .unused { color: red; }
+}
+
+@utility tab-4 {
+  tab-size: 4;
+}
+";
+    let source = set!["card", "md:tab-4", "hover:card", "m-2"];
+    let output = stylesheet::process(input, "input.css", &config_set, &source).unwrap();
+    assert_eq!(
+        output,
+        "h1 { font-size: 2rem; }
+.card {
+\tpadding: 1rem;
+\tdisplay: flex;
+}
+.card:hover {
+\tbackground-color: #ef4444;
+}
+.hover\\:card:hover {
+\tpadding: 1rem;
+\tdisplay: flex;
+}
+.hover\\:card:hover:hover {
+\tbackground-color: #ef4444;
+}
+.m-2 {
+\tmargin: 0.5rem;
+}
+@media (min-width: 768px) {
+\t.md\\:tab-4 {
+\t\ttab-size: 4;
+\t}
+}
+
+
+
+"
+    );
+
+    // A nested selector that an earlier declaration already uses as a property.
+    config_set
+        .variants
+        .insert("inverted".into(), vec![".inverted &".into()]);
+    let input =
+        "@tailwind utilities;\n@utility tab {\n  .inverted &: red;\n  @apply inverted:flex;\n}\n";
+    let error = stylesheet::process(input, "input.css", &config_set, &source).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "input.css:4: cannot `@apply` rules for `.inverted &`, it is already a declaration"
+    );
+
+    let input = "@tailwind utilities;\n@layer components {\n  .card { padding: 1rem; }\n}\n";
+    let error = stylesheet::process(input, "input.css", &config_set, &source).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "input.css:2: `@layer components` is used but no matching `@tailwind components` directive is present"
+    );
+}
+
+#[test]
+fn test_theme_functions() {
+    let config_set = config::parse(include_str!("default-config.json")).unwrap();
+    let source = HashSet::new();
+
+    let input = ".a {
+  color: theme(colors.blue.500);
+  background: theme('colors.blue.500 / 50%');
+  margin: theme(spacing[2.5]) theme(spacing.0.5);
+  width: theme(width.1/2);
+  font-size: theme(fontSize.xs);
+  padding: --spacing(4) --spacing(13);
+  content: \"theme(colors.red.500)\";
+}
+@media screen(md) {
+  .b { color: theme(colors.black); }
+}";
+    let output = stylesheet::process(input, "input.css", &config_set, &source).unwrap();
+    assert_eq!(
+        output,
+        ".a {
+  color: #3b82f6;
+  background: rgb(59 130 246 / 50%);
+  margin: 0.625rem 0.125rem;
+  width: 50%;
+  font-size: 0.75rem;
+  padding: 1rem calc(0.25rem * 13);
+  content: \"theme(colors.red.500)\";
+}
+@media (min-width: 768px) {
+  .b { color: #000; }
+}"
+    );
+
+    let input = ".café { color: theme(colors.black); }\n.→ { margin: --spacing(2); }";
+    let output = stylesheet::process(input, "input.css", &config_set, &source).unwrap();
+    assert_eq!(output, ".café { color: #000; }\n.→ { margin: 0.5rem; }");
+
+    let cases = vec![
+        (
+            ".a {\n  color: theme(colors.blue.5000);\n}",
+            "input.css:2: 'colors.blue.5000' does not exist in your theme config. 'blue' has the following valid keys: '50', '100', '200', '300', '400', '500', '600', '700', '800', '900'",
+        ),
+        (
+            "a { color: theme(colours.red) }",
+            "input.css:1: 'colours.red' does not exist in your theme config",
+        ),
+        (
+            "a { color: theme(colors.blue) }",
+            "input.css:1: 'colors.blue' was found but does not resolve to a value. Use one of its keys: '50', '100', '200', '300', '400', '500', '600', '700', '800', '900'",
+        ),
+        (
+            ".a {\n  color: theme(\n    colors.black\n  );\n  @apply not-a-class;\n}",
+            "input.css:5: cannot `@apply` unknown class `not-a-class`",
+        ),
+        (
+            "@media screen(xxl) {}",
+            "input.css:1: 'xxl' is not a screen, use one of: sm, md, lg, xl, 2xl",
+        ),
+    ];
+    for (input, expected) in cases {
+        let error = stylesheet::process(input, "input.css", &config_set, &source).unwrap_err();
+        assert_eq!(error.to_string(), expected);
+    }
+}
+
+#[test]
+fn test_css_variables() {
+    let mut config_set = config::parse(include_str!("default-config.json")).unwrap();
+    config_set.css_variables = true;
+
+    struct Buf(Rc<RefCell<Vec<String>>>);
+    impl Buffer for Buf {
+        fn write(&mut self, data: &str) {
+            self.0.borrow_mut().push(data.to_string())
+        }
+    }
+
+    let output = Rc::new(RefCell::new(Vec::new()));
+    let source = set![
+        "bg-blue-500/50",
+        "mt-4",
+        "-mx-0.5",
+        "text-xs",
+        "bg-black",
+        "w-1/2"
+    ];
+    write_css(Box::new(Buf(output.clone())), &config_set, &source);
+    assert_eq!(
+        output.borrow().clone(),
+        vec![
+            ":root {\n\t--color-black: #000;\n\t--color-blue-500: #3b82f6;\n\t--spacing-4: 1rem;\n\t--spacing-0_5: 0.125rem;\n\t--text-xs: 0.75rem;\n\t--text-xs--line-height:  1rem;\n}",
+            ".-mx-0\\.5 {\n\tmargin-left: calc(var(--spacing-0_5) * -1);\n\tmargin-right: calc(var(--spacing-0_5) * -1);\n}",
+            ".mt-4 {\n\tmargin-top: var(--spacing-4);\n}",
+            ".bg-black {\n\tbackground-color: var(--color-black);\n}",
+            ".bg-blue-500\\/50 {\n\tbackground-color: color-mix(in srgb, var(--color-blue-500) 50%, transparent);\n}",
+            ".text-xs {\n\tfont-size: var(--text-xs);\n\tline-height: var(--text-xs--line-height);\n}",
+        ]
+    );
+
+    // Variables used only through `@apply` are declared as well.
+    config_set.preflight = false;
+    let input = "@tailwind base;\n.btn {\n  @apply bg-red-500;\n}";
+    let output = stylesheet::process(input, "input.css", &config_set, &set!["mt-4"]).unwrap();
+    assert_eq!(
+        output,
+        ":root {\n\t--color-red-500: #ef4444;\n\t--spacing-4: 1rem;\n}\n.btn {\n  background-color: var(--color-red-500);\n}"
+    );
+
+    // Without a base directive they go before the first one.
+    let input = ".a { color: red; }\n@tailwind utilities;\n";
+    let output = stylesheet::process(
+        input,
+        "input.css",
+        &config_set,
+        &set!["mt-4", "bg-blue-500"],
+    )
+    .unwrap();
+    assert_eq!(
+        output,
+        ".a { color: red; }\n:root {\n\t--color-blue-500: #3b82f6;\n\t--spacing-4: 1rem;\n}\n.mt-4 {\n\tmargin-top: var(--spacing-4);\n}\n.bg-blue-500 {\n\tbackground-color: var(--color-blue-500);\n}\n"
+    );
+}
+
+#[test]
+fn test_user_config() {
+    let path = std::env::temp_dir().join("tailwindcss-rs-test-user-config.json");
+    std::fs::write(
+        &path,
+        r##"{
+            "theme": {
+                "screens": {"tablet": "640px"},
+                "extend": {
+                    "colors": {"brand": "#123456", "blue": {"450": "#5b8def"}},
+                    "spacing": {"18": "4.5rem"}
+                }
+            }
+        }"##,
+    )
+    .unwrap();
+    let config_set = config::load(Some(&path)).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    let test_case = vec![
+        ("bg-brand", ".bg-brand {\n\tbackground-color: #123456;\n}"),
+        (
+            "bg-blue-450",
+            ".bg-blue-450 {\n\tbackground-color: #5b8def;\n}",
+        ),
+        (
+            "bg-blue-500",
+            ".bg-blue-500 {\n\tbackground-color: #3b82f6;\n}",
+        ),
+        ("mt-18", ".mt-18 {\n\tmargin-top: 4.5rem;\n}"),
+        (
+            "tablet:flex",
+            "@media (min-width: 640px) {\n\t.tablet\\:flex {\n\t\tdisplay: flex;\n\t}\n}",
+        ),
+    ];
+
+    for (class, expected) in test_case {
+        assert_eq!(generate_css(&config_set, &set![class]), [expected]);
+    }
+    assert!(config_set.get_variant("md").is_none());
+
+    let path = std::env::temp_dir().join("tailwindcss-rs-test-missing-config.json");
+    assert!(config::load(Some(&path)).is_err());
+}
+
+#[test]
+fn test_config_formats() {
+    let load = |name: &str, source: &str| {
+        let path = std::env::temp_dir().join(name);
+        std::fs::write(&path, source).unwrap();
+        let config = config::load(Some(&path));
+        std::fs::remove_file(&path).unwrap();
+        config
+    };
+
+    let toml = load(
+        "tailwindcss-rs-test-config.toml",
+        r##"
+# Brand colors
+[theme.extend.colors]
+brand = "#123456"
+
+[theme.extend.spacing]
+"18" = "4.5rem"
+"##,
+    )
+    .unwrap();
+    let json5 = load(
+        "tailwindcss-rs-test-config.json5",
+        r##"{
+            // Brand colors
+            theme: {
+                extend: {
+                    colors: {brand: '#123456'},
+                    spacing: {'18': '4.5rem'},
+                },
+            },
+        }"##,
+    )
+    .unwrap();
+
+    for config_set in [&toml, &json5] {
+        assert_eq!(
+            generate_css(config_set, &set!["bg-brand"]),
+            [".bg-brand {\n\tbackground-color: #123456;\n}"]
+        );
+        assert_eq!(
+            generate_css(config_set, &set!["mt-18"]),
+            [".mt-18 {\n\tmargin-top: 4.5rem;\n}"]
+        );
+    }
+
+    let error = |name: &str, source: &str| {
+        let error = load(name, source).err().unwrap().to_string();
+        error[error.find(name).unwrap()..].to_string()
+    };
+    assert_eq!(
+        error(
+            "tailwindcss-rs-test-invalid.toml",
+            "[theme]\nscreens = {tablet = }\n"
+        ),
+        "tailwindcss-rs-test-invalid.toml:2:21: invalid string\nexpected `\"`, `'`"
+    );
+    assert!(
+        error("tailwindcss-rs-test-invalid.json5", "{\n  theme: {,}\n}")
+            .starts_with("tailwindcss-rs-test-invalid.json5:2:11: ")
+    );
+    assert_eq!(
+        error("tailwindcss-rs-test-invalid.json", "{\n  \"theme\": }"),
+        "tailwindcss-rs-test-invalid.json:2:12: expected value"
+    );
+}
+
+#[test]
+fn test_config_validation() {
+    let default_config = config::parse(include_str!("default-config.json")).unwrap();
+    assert!(default_config.diagnostics.is_empty());
+
+    let with = |changes: serde_json::Value| {
+        let mut obj: serde_json::Map<String, serde_json::Value> =
+            serde_json::from_str(include_str!("default-config.json")).unwrap();
+        obj.extend(changes.as_object().unwrap().clone());
+        config::parse(&serde_json::Value::Object(obj).to_string())
+    };
+
+    let config_set = with(serde_json::json!({
+        "spacing": {"1": "4", "2": "0.5rem"},
+        "font_size": {"huge": ["5rem", {"lineHeight": 1.1}], "tiny": "0.5rem"},
+        "spacin": {},
+        "colorz": {}
+    }))
+    .unwrap();
+    let diagnostics: Vec<String> = config_set
+        .diagnostics
+        .iter()
+        .map(ToString::to_string)
+        .collect();
+    assert_eq!(
+        diagnostics,
+        vec![
+            "spacing.1: `4` is not a valid CSS length",
+            "spacin: unknown config key, did you mean `spacing`?",
+            "colorz: unknown config key, did you mean `colors`?",
+        ]
+    );
+
+    let test_case = vec![
+        (
+            "text-huge",
+            ".text-huge {\n\tfont-size: 5rem;\n\tline-height: 1.1;\n}",
+        ),
+        ("text-tiny", ".text-tiny {\n\tfont-size: 0.5rem;\n}"),
+    ];
+
+    for (class, expected) in test_case {
+        assert_eq!(generate_css(&config_set, &set![class]), [expected]);
+    }
+
+    let error = with(serde_json::json!({
+        "font_size": {"huge": [5]},
+        "color": {"brand": 5, "blue": {"500": "#3b82f6"}},
+        "container": {"center": "yes", "padding": "2rem"},
+        "variants": {"hocus": ":hover"},
+        "display": []
+    }))
+    .err()
+    .unwrap();
+    assert_eq!(
+        error.to_string(),
+        [
+            "fontSize.huge: expected a size, `[size, lineHeight]` or `[size, { lineHeight }]`, found a list",
+            "colors.brand: expected a color or an object of shades, found a number",
+            "container.center: expected `true` or `false`, found a string",
+            "display: expected an object, found a list",
+            "variants.hocus: expected a selector with `&`, an at-rule or a list of selectors, found a string",
+        ]
+        .join("\n")
+    );
+
+    let error = with(serde_json::json!({
+        "break-before": {".break-before-x": {}, ".break-before-y": {"break-before": 5}}
+    }))
+    .err()
+    .unwrap();
+    assert_eq!(
+        error.to_string(),
+        "break-before..break-before-x: expected a style object of strings and numbers, found an object"
+    );
+
+    let mut config_set = with(serde_json::json!({
+        "break-before": {".break-before-y": {"break-before": 5}}
+    }))
+    .unwrap();
+    assert_eq!(
+        generate_css(&config_set, &set!["break-before-y"]),
+        [".break-before-y {\n\tbreak-before: 5;\n}"]
+    );
+    config_set.break_point["break-before"].insert(".break-before-x".into(), serde_json::json!({}));
+    assert!(generate_css(&config_set, &set!["break-before-x"]).is_empty());
+
+    assert_eq!(
+        config::parse("[]").err().unwrap().to_string(),
+        "the config must be an object"
+    );
+    assert!(config::parse("{").is_err());
+}
+
+#[test]
+fn test_prefix_and_separator() {
+    let mut config_set = config::parse(include_str!("default-config.json")).unwrap();
+    config_set.prefix = "tw-".into();
+    config_set.separator = "_".into();
+
+    let test_case = vec![
+        ("tw-mt-4", ".tw-mt-4 {\n\tmargin-top: 1rem;\n}"),
+        ("-tw-mt-4", ".-tw-mt-4 {\n\tmargin-top: -1rem;\n}"),
+        (
+            "hover_tw-bg-red-500",
+            ".hover_tw-bg-red-500:hover {\n\tbackground-color: #ef4444;\n}",
+        ),
+        (
+            "md_hover_tw-flex",
+            "@media (min-width: 768px) {\n\t.md_hover_tw-flex:hover {\n\t\tdisplay: flex;\n\t}\n}",
+        ),
+        (
+            "tw-bg-[url(a_b.png)]",
+            ".tw-bg-\\[url\\(a_b\\.png\\)\\] {\n\tbackground-image: url(a_b.png);\n}",
+        ),
+    ];
+
+    for (class, expected) in test_case {
+        assert_eq!(generate_css(&config_set, &set![class]), [expected]);
+    }
+
+    let source = set!["mt-4", "hover:tw-flex", "tw-hover_flex"];
+    assert!(generate_css(&config_set, &source).is_empty());
+
+    config_set.separator = "__".into();
+    assert_eq!(
+        generate_css(&config_set, &set!["focus__tw-block"]),
+        [".focus__tw-block:focus {\n\tdisplay: block;\n}"]
+    );
+
+    // Classes of the stylesheet are used as written, without the prefix.
+    let input = "@tailwind components;\n@tailwind utilities;\n@layer components {\n  .card { padding: 1rem; }\n}\n@utility tab-4 {\n  tab-size: 4;\n}\n";
+    let source = set![
+        "card",
+        "tw-card",
+        "hover__card",
+        "tab-4",
+        "tw-tab-4",
+        "tw-mt-4"
+    ];
+    let output = stylesheet::process(input, "input.css", &config_set, &source).unwrap();
+    assert_eq!(
+        output,
+        ".card {\n\tpadding: 1rem;\n}\n.hover__card:hover {\n\tpadding: 1rem;\n}\n.tw-mt-4 {\n\tmargin-top: 1rem;\n}\n.tab-4 {\n\ttab-size: 4;\n}\n"
+    );
+}
+
+#[test]
+fn test_safelist_and_blocklist() {
+    let with = |changes: serde_json::Value| {
+        let mut obj: serde_json::Map<String, serde_json::Value> =
+            serde_json::from_str(include_str!("default-config.json")).unwrap();
+        obj.extend(changes.as_object().unwrap().clone());
+        config::parse(&serde_json::Value::Object(obj).to_string())
+    };
+
+    let config_set = with(serde_json::json!({
+        "safelist": [
+            "flex",
+            "/^mt-(2|4)$/",
+            {"pattern": "bg-(red|green)-(100|500)", "variants": ["hover"]}
+        ],
+        "blocklist": ["block", "/^opacity-/"]
+    }))
+    .unwrap();
+    let classes = select_classes(&config_set, &set!["block", "opacity-50", "p-4"]);
+    let mut classes: Vec<&str> = classes.iter().map(String::as_str).collect();
+    classes.sort_unstable();
+    assert_eq!(
+        classes,
+        vec![
+            "bg-green-100",
+            "bg-green-500",
+            "bg-red-100",
+            "bg-red-500",
+            "flex",
+            "hover:bg-green-100",
+            "hover:bg-green-500",
+            "hover:bg-red-100",
+            "hover:bg-red-500",
+            "mt-2",
+            "mt-4",
+            "p-4",
+        ]
+    );
+
+    let mut prefixed = config_set.clone();
+    prefixed.prefix = "tw-".into();
+    prefixed.separator = "_".into();
+    prefixed.safelist = with(serde_json::json!({
+        "safelist": [{"pattern": "^tw-bg-red-500$", "variants": ["md"]}]
+    }))
+    .unwrap()
+    .safelist;
+    assert_eq!(
+        select_classes(&prefixed, &HashSet::new()),
+        set!["tw-bg-red-500", "md_tw-bg-red-500"]
+    );
+
+    let error = with(serde_json::json!({
+        "safelist": [5, {"pattern": "bg-(red"}, {"pattern": "x", "variants": ["hovr"]}],
+        "blocklist": "block"
+    }))
+    .err()
+    .unwrap();
+    assert_eq!(
+        error.to_string(),
+        [
+            "safelist[0]: expected a class, a `/pattern/` or `{ pattern, variants }`, found a number",
+            "safelist[1]: invalid pattern `bg-(red`: unclosed group",
+            "blocklist: expected a list, found a string",
+        ]
+        .join("\n")
+    );
+    match error {
+        config::Error::Invalid(diagnostics) => {
+            assert!(diagnostics
+                .iter()
+                .any(|diagnostic| diagnostic.to_string() == "safelist[2]: unknown variant `hovr`"))
+        }
+        _ => panic!("expected diagnostics"),
+    }
+}
+
+#[test]
+fn test_core_plugins() {
+    let with = |core_plugins: serde_json::Value| {
+        let mut obj: serde_json::Map<String, serde_json::Value> =
+            serde_json::from_str(include_str!("default-config.json")).unwrap();
+        obj.insert("corePlugins".into(), core_plugins);
+        config::parse(&serde_json::Value::Object(obj).to_string())
+    };
+
+    struct Collect(Rc<RefCell<Vec<String>>>);
+    impl Buffer for Collect {
+        fn write(&mut self, data: &str) {
+            self.0.borrow_mut().push(data.to_string());
+        }
+    }
+    let generate = |config_set: &config::Config, source: HashSet<String>| {
+        let output = Rc::new(RefCell::new(Vec::new()));
+        write_css(Box::new(Collect(output.clone())), config_set, &source);
+        let output = output.borrow().clone();
+        output
+    };
+
+    let config_set =
+        with(serde_json::json!({"float": false, "columns": false, "zIndex": false})).unwrap();
+    assert!(config_set.preflight);
+    assert!(config_set.diagnostics.is_empty());
+    assert_eq!(
+        generate(
+            &config_set,
+            set![
+                "float-left",
+                "columns-2",
+                "z-10",
+                "hover:float-right",
+                "flex"
+            ]
+        ),
+        vec![".flex {\n\tdisplay: flex;\n}"]
+    );
+
+    // Tailwind's plugin names.
+    let config_set = with(serde_json::json!({"breakBefore": false, "flexBasis": false})).unwrap();
+    assert!(config_set.diagnostics.is_empty());
+    assert_eq!(
+        generate(
+            &config_set,
+            set!["break-before-page", "break-after-page", "basis-4"]
+        ),
+        vec![".break-after-page {\n\tbreak-after: page;\n}"]
+    );
+
+    let mut config_set = with(serde_json::json!(["margin", "background-color"])).unwrap();
+    assert!(!config_set.preflight);
+    config_set
+        .components
+        .insert(".card".into(), serde_json::json!({"padding": "1rem"}));
+    assert_eq!(
+        generate(
+            &config_set,
+            set![
+                "m-4",
+                "p-4",
+                "flex",
+                "bg-red-500",
+                "text-lg",
+                "container",
+                "card"
+            ]
+        ),
+        vec![
+            ".card {\n\tpadding: 1rem;\n}",
+            ".m-4 {\n\tmargin: 1rem;\n}",
+            ".bg-red-500 {\n\tbackground-color: #ef4444;\n}",
+        ]
+    );
+
+    let config_set = with(serde_json::json!({"colums": false})).unwrap();
+    assert_eq!(
+        config_set.diagnostics[0].to_string(),
+        "corePlugins.colums: unknown core plugin `colums`, did you mean `columns`?"
+    );
+    assert_eq!(
+        with(serde_json::json!({"float": "no"}))
+            .err()
+            .unwrap()
+            .to_string(),
+        "corePlugins.float: expected `true` or `false`, found a string"
+    );
+}
+
+#[test]
+fn test_presets() {
+    let dir = std::env::temp_dir().join("tailwindcss-rs-test-presets");
+    std::fs::create_dir_all(dir.join("presets")).unwrap();
+    let write = |name: &str, source: &str| std::fs::write(dir.join(name), source).unwrap();
+    write(
+        "presets/base.json",
+        r##"{
+            "theme": {
+                "screens": {"tablet": "640px"},
+                "extend": {"colors": {"brand": "#111111"}}
+            },
+            "plugins": {"width": [["w", ["width"]], ["size", ["width", "height"]]]},
+            "corePlugins": {"float": false, "columns": false}
+        }"##,
+    );
+    write(
+        "presets/brand.toml",
+        r##"
+presets = ["./base.json"]
+corePlugins = { columns = true }
+
+[theme.extend.colors]
+accent = "#222222"
+"##,
+    );
+    write(
+        "tailwind.config.json",
+        r##"{
+            "presets": ["./presets/brand.toml"],
+            "theme": {"colors": {"white": "#ffffff"}},
+            "corePlugins": {"zIndex": false}
+        }"##,
+    );
+    write("loop.json", r#"{"presets": ["./presets/../loop.json"]}"#);
+    write("invalid.json", r#"{"presets": "./presets/base.json"}"#);
+
+    let config_set = config::load(Some(&dir.join("tailwind.config.json")));
+    let cycle = config::load(Some(&dir.join("loop.json"))).err().unwrap();
+    let invalid = config::load(Some(&dir.join("invalid.json"))).err().unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+    let config_set = config_set.unwrap();
+
+    // The user `colors` replace the palette, the `extend` of every preset
+    // still applies on top of it.
+    assert_eq!(
+        config_set.color.keys().collect::<Vec<_>>(),
+        ["white", "brand", "accent"]
+    );
+    assert_eq!(config_set.screens.keys().collect::<Vec<_>>(), ["tablet"]);
+    assert!(config_set.plugins.contains_key("margin"));
+    assert!(config_set.diagnostics.is_empty());
+
+    struct Collect(Rc<RefCell<Vec<String>>>);
+    impl Buffer for Collect {
+        fn write(&mut self, data: &str) {
+            self.0.borrow_mut().push(data.to_string());
+        }
+    }
+    let output = Rc::new(RefCell::new(Vec::new()));
+    write_css(
+        Box::new(Collect(output.clone())),
+        &config_set,
+        &set![
+            "float-left",
+            "columns-2",
+            "z-10",
+            "size-4",
+            "m-4",
+            "bg-accent"
+        ],
+    );
+    assert_eq!(
+        *output.borrow(),
+        vec![
+            ".m-4 {\n\tmargin: 1rem;\n}",
+            ".size-4 {\n\twidth: 1rem;\n\theight: 1rem;\n}",
+            ".columns-2 {\n\tcolumns: 2;\n}",
+            ".bg-accent {\n\tbackground-color: #222222;\n}",
+        ]
+    );
+
+    assert!(cycle
+        .to_string()
+        .ends_with("loop.json: the preset includes itself"));
+    assert!(invalid
+        .to_string()
+        .ends_with("invalid.json: `presets` must be a list of file paths"));
+}
+
+#[test]
+fn test_generated_palette() {
+    let with = |brand: serde_json::Value| {
+        let mut obj: serde_json::Map<String, serde_json::Value> =
+            serde_json::from_str(include_str!("default-config.json")).unwrap();
+        obj["color"]
+            .as_object_mut()
+            .unwrap()
+            .insert("brand".into(), brand);
+        config::parse(&serde_json::Value::Object(obj).to_string())
+    };
+
+    let config_set = with(serde_json::json!({"generate": "#3b82f6", "950": "#0b1a3a"})).unwrap();
+    let shades = config_set.get_color_map("brand").unwrap();
+    assert_eq!(
+        shades.keys().collect::<Vec<_>>(),
+        ["50", "100", "200", "300", "400", "500", "600", "700", "800", "900", "950"]
+    );
+    assert_eq!(shades["50"], "oklch(97.5% 0.012 259.81)");
+    assert_eq!(shades["500"], "oklch(62.3% 0.188 259.81)");
+    assert_eq!(shades["900"], "oklch(36.1% 0.122 259.81)");
+    assert_eq!(shades["950"], "#0b1a3a");
+    assert_eq!(
+        config_set.get_theme("colors.brand.500").unwrap(),
+        "oklch(62.3% 0.188 259.81)"
+    );
+
+    struct Collect(Rc<RefCell<Vec<String>>>);
+    impl Buffer for Collect {
+        fn write(&mut self, data: &str) {
+            self.0.borrow_mut().push(data.to_string());
+        }
+    }
+    let output = Rc::new(RefCell::new(Vec::new()));
+    write_css(
+        Box::new(Collect(output.clone())),
+        &config_set,
+        &set!["bg-brand-500/50"],
+    );
+    assert_eq!(
+        *output.borrow(),
+        vec![".bg-brand-500\\/50 {\n\tbackground-color: oklch(62.3% 0.188 259.81 / 0.5);\n}"]
+    );
+
+    // Shades stay in sRGB and grays stay gray.
+    let gray = with(serde_json::json!({"generate": "#888"})).unwrap();
+    assert!(gray
+        .get_color_map("brand")
+        .unwrap()
+        .values()
+        .all(|shade| shade.ends_with(" 0 0)")));
+
+    let error = |brand| with(brand).err().unwrap().to_string();
+    assert_eq!(
+        error(serde_json::json!({"generate": "blue"})),
+        "colors.brand.generate: `blue` is not a hex color"
+    );
+    assert_eq!(
+        error(serde_json::json!({"generate": 5})),
+        "colors.brand.generate: expected a hex color, found a number"
+    );
+}
+
+#[test]
+fn test_design_tokens() {
+    let dir = std::env::temp_dir().join("tailwindcss-rs-test-tokens");
+    std::fs::create_dir_all(&dir).unwrap();
+    let write = |name: &str, source: &str| std::fs::write(dir.join(name), source).unwrap();
+    write(
+        "tokens.json",
+        r##"{
+            "color": {
+                "$type": "color",
+                "blue": {"500": {"$value": "#3b82f6"}},
+                "brand": {"$value": "{color.blue.500}"},
+                "overlay": {"$value": {"colorSpace": "srgb", "components": [0, 0, 0], "alpha": 0.5}}
+            },
+            "spacing": {"$type": "dimension", "gutter": {"$value": {"value": 1.5, "unit": "rem"}}},
+            "font": {
+                "family": {"$type": "fontFamily", "display": {"$value": ["Inter Display", "sans-serif"]}},
+                "weight": {"$type": "fontWeight", "heavy": {"$value": "extra-bold"}},
+                "size": {"$type": "dimension", "hero": {"$value": "3.5rem"}}
+            },
+            "shadow": {
+                "card": {
+                    "$type": "shadow",
+                    "$value": {"color": "{color.overlay}", "offsetX": "0px", "offsetY": "1px", "blur": "3px", "spread": "0px"}
+                }
+            },
+            "motion": {"$type": "duration", "quick": {"$value": {"value": 120, "unit": "ms"}}},
+            "easing": {"$type": "cubicBezier", "standard": {"$value": [0.2, 0, 0, 1]}}
+        }"##,
+    );
+    write(
+        "tailwind.config.json",
+        r#"{"tokens": "./tokens.json", "theme": {"extend": {"spacing": {"gutter": "2rem"}}}}"#,
+    );
+    write(
+        "cycle.json",
+        r#"{"size": {"$type": "dimension", "a": {"$value": "{size.b}"}, "b": {"$value": "{size.a}"}}}"#,
+    );
+    write(
+        "missing.json",
+        r#"{"color": {"a": {"$value": "{color.b}"}}}"#,
+    );
+
+    let theme = config::read_tokens(&dir.join("tokens.json"));
+    let config_set = config::load(Some(&dir.join("tailwind.config.json")));
+    let cycle = config::read_tokens(&dir.join("cycle.json")).err().unwrap();
+    let missing = config::read_tokens(&dir.join("missing.json"))
+        .err()
+        .unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(
+        serde_json::Value::Object(theme.unwrap()),
+        serde_json::json!({
+            "colors": {
+                "blue": {"500": "#3b82f6"},
+                "brand": "#3b82f6",
+                "overlay": "rgb(0 0 0 / 0.5)"
+            },
+            "spacing": {"gutter": "1.5rem"},
+            "fontSize": {"hero": "3.5rem"},
+            "fontFamily": {"display": ["\"Inter Display\"", "sans-serif"]},
+            "fontWeight": {"heavy": "800"},
+            "boxShadow": {"card": "0px 1px 3px 0px rgb(0 0 0 / 0.5)"},
+            "transitionDuration": {"quick": "120ms"}
+        })
+    );
+
+    // Tokens extend the theme, below what the config writes itself.
+    let config_set = config_set.unwrap();
+    assert!(config_set.diagnostics.is_empty());
+    assert_eq!(config_set.spacing["gutter"], "2rem");
+    assert_eq!(config_set.spacing["4"], "1rem");
+    assert_eq!(config_set.get_color_str("brand").unwrap(), "#3b82f6");
+    assert!(config_set.get_color_map("red").is_some());
+
+    struct Collect(Rc<RefCell<Vec<String>>>);
+    impl Buffer for Collect {
+        fn write(&mut self, data: &str) {
+            self.0.borrow_mut().push(data.to_string());
+        }
+    }
+    let output = Rc::new(RefCell::new(Vec::new()));
+    write_css(
+        Box::new(Collect(output.clone())),
+        &config_set,
+        &set![
+            "font-display",
+            "font-heavy",
+            "text-hero",
+            "shadow-card",
+            "duration-quick"
+        ],
+    );
+    assert_eq!(
+        *output.borrow(),
+        vec![
+            ".font-display {\n\tfont-family: \"Inter Display\", sans-serif;\n}",
+            ".text-hero {\n\tfont-size: 3.5rem;\n}",
+            ".font-heavy {\n\tfont-weight: 800;\n}",
+            ".shadow-card {\n\tbox-shadow: 0px 1px 3px 0px rgb(0 0 0 / 0.5);\n}",
+            ".duration-quick {\n\ttransition-duration: 120ms;\n}",
+        ]
+    );
+
+    assert!(cycle
+        .to_string()
+        .ends_with("cycle.json: size.a: `{size.a}` is part of an alias cycle"));
+    assert!(missing
+        .to_string()
+        .ends_with("missing.json: color.a: `{color.b}` doesn't refer to a token"));
+}
+
+#[test]
+fn test_config_utilities_and_variants() {
+    let with = |extra: serde_json::Value| {
+        let mut obj: serde_json::Map<String, serde_json::Value> =
+            serde_json::from_str(include_str!("default-config.json")).unwrap();
+        for (key, value) in extra.as_object().unwrap() {
+            match (obj.get_mut(key), value) {
+                (Some(serde_json::Value::Object(section)), serde_json::Value::Object(value)) => {
+                    section.extend(value.clone())
+                }
+                _ => {
+                    obj.insert(key.clone(), value.clone());
+                }
+            }
+        }
+        config::parse(&serde_json::Value::Object(obj).to_string())
+    };
+
+    let config_set = with(serde_json::json!({
+        "tabSize": {"DEFAULT": "4", "2": "2"},
+        "utilities": {
+            "content-auto": {"content-visibility": "auto"},
+            "tab": {"property": "tab-size", "values": "theme.tabSize"},
+            "nudge": {"property": ["top", "left"], "values": {"1": "1px"}, "supportsNegative": true},
+            "glow": {"property": "--glow", "values": "theme.colors"}
+        },
+        "variants": {"hocus": ["&:hover", "&:focus"]}
+    }))
+    .unwrap();
+    assert!(config_set.diagnostics.is_empty());
+
+    struct Collect(Rc<RefCell<Vec<String>>>);
+    impl Buffer for Collect {
+        fn write(&mut self, data: &str) {
+            self.0.borrow_mut().push(data.to_string());
+        }
+    }
+    let output = Rc::new(RefCell::new(Vec::new()));
+    write_css(
+        Box::new(Collect(output.clone())),
+        &config_set,
+        &set![
+            "content-auto",
+            "tab",
+            "tab-2",
+            "-tab-2",
+            "-nudge-1",
+            "glow-red-500",
+            "hocus:content-auto",
+            "md:hocus:tab-2"
+        ],
+    );
+    assert_eq!(
+        *output.borrow(),
+        vec![
+            ".content-auto {\n\tcontent-visibility: auto;\n}",
+            ".tab {\n\ttab-size: 4;\n}",
+            ".tab-2 {\n\ttab-size: 2;\n}",
+            ".-nudge-1 {\n\ttop: -1px;\n\tleft: -1px;\n}",
+            ".glow-red-500 {\n\t--glow: #ef4444;\n}",
+            ".hocus\\:content-auto:hover, .hocus\\:content-auto:focus {\n\tcontent-visibility: auto;\n}",
+            "@media (min-width: 768px) {\n\t.md\\:hocus\\:tab-2:hover, .md\\:hocus\\:tab-2:focus {\n\t\ttab-size: 2;\n\t}\n}",
+        ]
+    );
+
+    let error = |extra| with(extra).err().unwrap().to_string();
+    assert_eq!(
+        error(serde_json::json!({
+            "utilities": {
+                "tab": {"property": "tab-size", "values": "theme.tabSize", "supportNegative": true}
+            }
+        })),
+        "utilities.tab.values: `theme.tabSize` is not in the theme\n\
+         utilities.tab.supportNegative: unknown utility option, did you mean `supportsNegative`?"
+    );
+    assert_eq!(
+        error(serde_json::json!({"variants": {"print-only": ["@media print"]}})),
+        "variants.print-only: expected a selector with `&`, an at-rule or a list of selectors, found a list"
+    );
+}